@@ -0,0 +1,71 @@
+//! Attack lookups through the tables generated at compile time, against the code they
+//! replaced: the same tables behind `Lazy`, built on first use, the sliders' as `Vec<BitBoard>`.
+use std::convert::TryFrom;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use once_cell::sync::Lazy;
+use dogfish::board_representation::bitboard::BitBoard;
+use dogfish::board_representation::square::Square;
+use dogfish::common::prng::Prng;
+use dogfish::piece::attacks::{king, knight};
+use dogfish::piece::attacks::magic::{attack_table, populate_magic, Magic, BISHOP_DIRECTIONS, ROOK_DIRECTIONS};
+use dogfish::piece::attacks::magic_numbers::{MAGIC_NUMBERS_BISHOP, MAGIC_NUMBERS_ROOK};
+use dogfish::piece::piecetype::PieceType;
+
+static LAZY_KNIGHT: Lazy<[BitBoard; 64]> = Lazy::new(knight::generate);
+static LAZY_KING: Lazy<[BitBoard; 64]> = Lazy::new(king::generate);
+
+/// The slider tables as they were before build-time generation.
+struct LazySliding {
+    table: Vec<BitBoard>,
+    magic: [Magic; 64],
+}
+
+static LAZY_ROOK: Lazy<LazySliding> = Lazy::new(|| LazySliding {
+    table: attack_table(ROOK_DIRECTIONS, &MAGIC_NUMBERS_ROOK),
+    magic: populate_magic(ROOK_DIRECTIONS, &MAGIC_NUMBERS_ROOK),
+});
+static LAZY_BISHOP: Lazy<LazySliding> = Lazy::new(|| LazySliding {
+    table: attack_table(BISHOP_DIRECTIONS, &MAGIC_NUMBERS_BISHOP),
+    magic: populate_magic(BISHOP_DIRECTIONS, &MAGIC_NUMBERS_BISHOP),
+});
+
+/// The old `rook_attack` and `bishop_attack`.
+fn lazy_slider(tables: &Lazy<LazySliding>, square: Square, occupancy: BitBoard) -> BitBoard {
+    let magic = tables.magic[square.value() as usize];
+    let idx: usize = u64::from(
+        ((occupancy & magic.mask()) * (magic.magic().into())) >> (magic.shift().into())
+    ) as usize;
+    tables.table[magic.table() + idx]
+}
+
+/// Squares with sparse random occupancies, like those of a middlegame.
+fn positions() -> Vec<(Square, BitBoard)> {
+    let mut prng = Prng::new(0x0062_656e_6368);
+    (0..1024)
+        .map(|i| {
+            let square = Square::try_from(i % 64).unwrap();
+            (square, (prng.next_u64() & prng.next_u64()).into())
+        })
+        .collect()
+}
+
+fn attacks(c: &mut Criterion) {
+    let positions = positions();
+    let xor = |f: &dyn Fn(Square, BitBoard) -> BitBoard| {
+        positions.iter().fold(BitBoard::new(0), |acc, (square, occupancy)| acc ^ f(*square, black_box(*occupancy)))
+    };
+
+    c.bench_function("knight static", |b| b.iter(|| xor(&|sq, _| PieceType::knight_attack(sq))));
+    c.bench_function("knight lazy", |b| b.iter(|| xor(&|sq, _| LAZY_KNIGHT[sq.value() as usize])));
+    c.bench_function("king static", |b| b.iter(|| xor(&|sq, _| PieceType::king_attack(sq))));
+    c.bench_function("king lazy", |b| b.iter(|| xor(&|sq, _| LAZY_KING[sq.value() as usize])));
+    c.bench_function("bishop static", |b| b.iter(|| xor(&PieceType::bishop_attack)));
+    c.bench_function("bishop lazy", |b| b.iter(|| xor(&|sq, occ| lazy_slider(&LAZY_BISHOP, sq, occ))));
+    c.bench_function("rook static", |b| b.iter(|| xor(&PieceType::rook_attack)));
+    c.bench_function("rook lazy", |b| b.iter(|| xor(&|sq, occ| lazy_slider(&LAZY_ROOK, sq, occ))));
+    // The cost the first lookup used to pay, and that the static tables no longer have
+    c.bench_function("rook table build", |b| b.iter(|| attack_table(ROOK_DIRECTIONS, &MAGIC_NUMBERS_ROOK)));
+}
+
+criterion_group!(benches, attacks);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+//! Generates the rook and bishop attack tables indexed by the magic numbers, so that they
+//! are read-only data in the binary instead of being built on first use.
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+#[path = "src/piece/attacks/slider.rs"]
+mod slider;
+#[path = "src/piece/attacks/magic_numbers.rs"]
+mod magic_numbers;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/piece/attacks/slider.rs");
+    println!("cargo:rerun-if-changed=src/piece/attacks/magic_numbers.rs");
+
+    let mut out = String::new();
+    write_table(&mut out, "ROOK_ATTACKS", &slider::attack_table(slider::ROOK_STEPS, &magic_numbers::MAGIC_NUMBERS_ROOK));
+    write_table(&mut out, "BISHOP_ATTACKS", &slider::attack_table(slider::BISHOP_STEPS, &magic_numbers::MAGIC_NUMBERS_BISHOP));
+
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("sliding_attacks.rs");
+    fs::write(path, out).unwrap();
+}
+
+/// Writes one table as a static `u64` array.
+fn write_table(out: &mut String, name: &str, table: &[u64]) {
+    writeln!(out, "static {}: [u64; {}] = [", name, table.len()).unwrap();
+    for row in table.chunks(8) {
+        let row: Vec<String> = row.iter().map(|attacks| format!("{:#x}", attacks)).collect();
+        writeln!(out, "    {},", row.join(", ")).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+}
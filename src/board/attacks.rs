@@ -0,0 +1,175 @@
+use crate::board::Board;
+use crate::board_representation::bitboard::BitBoard;
+use crate::board_representation::square::Square;
+use crate::piece::attacks::lines::between;
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+
+impl Board {
+    /// Pieces of both colours that attack `square`, with sliders blocked by `occupancy`.
+    /// Passing an occupancy other than the board's own lets callers look through pieces,
+    /// e.g. a king that is about to step away along the line of a checking slider.
+    pub fn attackers_to(&self, square: Square, occupancy: BitBoard) -> BitBoard {
+        let queens = self.bb_pieces(PieceType::Q);
+        let white_pawns = self.bb_pieces_of(Colour::White, PieceType::P);
+        let black_pawns = self.bb_pieces_of(Colour::Black, PieceType::P);
+
+        (PieceType::knight_attack(square) & self.bb_pieces(PieceType::N))
+            | (PieceType::king_attack(square) & self.bb_pieces(PieceType::K))
+            // A pawn attacks the square if a pawn of the other colour there would attack it
+            | (PieceType::pawn_attack(square.into(), Colour::Black) & white_pawns)
+            | (PieceType::pawn_attack(square.into(), Colour::White) & black_pawns)
+            | (PieceType::bishop_attack(square, occupancy) & (self.bb_pieces(PieceType::B) | queens))
+            | (PieceType::rook_attack(square, occupancy) & (self.bb_pieces(PieceType::R) | queens))
+    }
+
+    /// Whether any piece of colour `by` attacks `square` in the current position.
+    pub fn is_square_attacked(&self, square: Square, by: Colour) -> bool {
+        self.attacked_by(square, by, self.occupancy())
+    }
+
+    /// The enemy pieces giving check to the side to move.
+    pub fn checkers(&self) -> BitBoard {
+        match self.king_square(self.player) {
+            Some(king) => self.attackers_by(king, self.player.opposite(), self.occupancy()),
+            None => 0.into(),
+        }
+    }
+
+    /// Whether the side to move is in check.
+    pub fn in_check(&self) -> bool {
+        !self.checkers().is_empty()
+    }
+
+    /// Pieces of either colour that are the only piece between `colour`'s king and an
+    /// enemy slider. Moving one of `colour`'s away exposes the king; moving one of the
+    /// opponent's away gives a discovered check.
+    pub fn blockers_for_king(&self, colour: Colour) -> BitBoard {
+        self.slider_blockers(colour).0
+    }
+
+    /// Pieces of `colour` that are pinned against their own king.
+    pub fn pinned(&self, colour: Colour) -> BitBoard {
+        self.blockers_for_king(colour) & self.bb_player[colour as usize]
+    }
+
+    /// Enemy sliders pinning a piece of `colour` against its king.
+    pub fn pinners(&self, colour: Colour) -> BitBoard {
+        self.slider_blockers(colour).1
+    }
+
+    /// The blockers for `colour`'s king, and the enemy sliders pinning `colour`'s pieces.
+    fn slider_blockers(&self, colour: Colour) -> (BitBoard, BitBoard) {
+        let mut blockers: BitBoard = 0.into();
+        let mut pinners: BitBoard = 0.into();
+        let king = match self.king_square(colour) {
+            Some(king) => king,
+            None => return (blockers, pinners),
+        };
+        let them = colour.opposite();
+        let occupancy = self.occupancy();
+        let queens = self.bb_pieces_of(them, PieceType::Q);
+
+        // Enemy sliders that would attack the king on an empty board
+        let snipers = (PieceType::rook_attack(king, 0.into()) & (self.bb_pieces_of(them, PieceType::R) | queens))
+            | (PieceType::bishop_attack(king, 0.into()) & (self.bb_pieces_of(them, PieceType::B) | queens));
+
+        for sniper in snipers.iter_squares() {
+            let between = between(king, sniper) & occupancy;
+            if between.count() == 1 {
+                blockers |= between;
+                if !(between & self.bb_player[colour as usize]).is_empty() {
+                    pinners |= BitBoard::from(sniper);
+                }
+            }
+        }
+        (blockers, pinners)
+    }
+
+    /// Pieces of colour `by` that attack `square`, given the occupancy.
+    pub(crate) fn attackers_by(&self, square: Square, by: Colour, occupancy: BitBoard) -> BitBoard {
+        self.attackers_to(square, occupancy) & self.bb_player[by as usize]
+    }
+
+    /// Whether any piece of colour `by` attacks `square`, given the occupancy.
+    pub(crate) fn attacked_by(&self, square: Square, by: Colour, occupancy: BitBoard) -> bool {
+        !self.attackers_by(square, by, occupancy).is_empty()
+    }
+
+    pub(crate) fn king_square(&self, colour: Colour) -> Option<Square> {
+        self.bb_pieces_of(colour, PieceType::K).iter_squares().next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board_representation::bitboard::BitBoard;
+    use crate::piece::colour::Colour;
+    use crate::testing::{square, squares};
+
+    #[test]
+    fn attackers_to_square() {
+        let board: Board = "4k3/8/2n2b2/3p4/4P3/5N2/1B6/4RK2 w - - 0 1".parse().unwrap();
+        let e4 = square("e4");
+
+        assert_eq!(board.attackers_to(e4, board.occupancy()), squares(&["d5", "e1"]));
+        // Looking through the pawn on e4, the rook also attacks e5
+        let e5 = square("e5");
+        assert_eq!(board.attackers_to(e5, board.occupancy()), squares(&["c6", "f6", "f3", "b2"]));
+        assert_eq!(board.attackers_to(e5, board.occupancy() ^ BitBoard::from(e4)), squares(&["c6", "f6", "f3", "b2", "e1"]));
+
+        let d4 = square("d4");
+        assert_eq!(board.attackers_to(d4, board.occupancy()), squares(&["c6", "f3", "b2", "f6"]));
+        assert_eq!(board.attackers_to(d4, board.occupancy()) & board.bb_player(Colour::White), squares(&["f3", "b2"]));
+    }
+
+    #[test]
+    fn pins_and_blockers() {
+        // The knight on e2 is pinned by the rook on e8; the black bishop on d4 blocks the
+        // queen on b2 from the black king on g7; the white bishop on f3 blocks nothing
+        let board: Board = "4r3/6k1/8/8/3b4/8/1Q2NB2/4K3 w - - 0 1".parse().unwrap();
+
+        assert_eq!(board.pinned(Colour::White), squares(&["e2"]));
+        assert_eq!(board.pinners(Colour::White), squares(&["e8"]));
+        assert_eq!(board.blockers_for_king(Colour::White), squares(&["e2"]));
+
+        assert_eq!(board.pinned(Colour::Black), squares(&["d4"]));
+        assert_eq!(board.pinners(Colour::Black), squares(&["b2"]));
+
+        // Two pieces in between: no pin
+        let board: Board = "4r3/6k1/8/8/4P3/8/4N3/4K3 w - - 0 1".parse().unwrap();
+        assert!(board.pinned(Colour::White).is_empty());
+        assert!(board.pinners(Colour::White).is_empty());
+
+        // An enemy piece in between is a discovered check blocker, not a pin
+        let board: Board = "4r3/6k1/8/8/4n3/8/8/4K3 w - - 0 1".parse().unwrap();
+        assert_eq!(board.blockers_for_king(Colour::White), squares(&["e4"]));
+        assert!(board.pinned(Colour::White).is_empty());
+    }
+
+    #[test]
+    fn square_attacked() {
+        let board: Board = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1".parse().unwrap();
+        assert!(board.is_square_attacked(square("a8"), Colour::White));
+        assert!(board.is_square_attacked(square("d8"), Colour::Black));
+        assert!(!board.is_square_attacked(square("b2"), Colour::White));
+        assert!(!board.is_square_attacked(square("e2"), Colour::Black));
+    }
+
+    #[test]
+    fn checkers_and_in_check() {
+        let board: Board = "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2".parse().unwrap();
+        assert!(!board.in_check());
+        assert!(board.checkers().is_empty());
+
+        // Fool's mate
+        let board: Board = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".parse().unwrap();
+        assert!(board.in_check());
+        assert_eq!(board.checkers(), squares(&["h4"]));
+
+        // Double check from a knight and a rook
+        let board: Board = "4r2k/8/8/8/8/3n4/8/4K3 w - - 0 1".parse().unwrap();
+        assert_eq!(board.checkers(), squares(&["e8", "d3"]));
+    }
+}
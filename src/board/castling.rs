@@ -1,6 +1,58 @@
-#[derive(Copy, Clone)]
+use std::convert::TryFrom;
+use crate::board::Board;
+use crate::board::moves::Move;
+use crate::board_representation::square::Square;
+use crate::piece::colour::Colour;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 #[repr(u8)]
 pub enum CastlingRights {
     QueenSide = 0,
     KingSide = 1
-}
\ No newline at end of file
+}
+
+impl CastlingRights {
+    /// The file the king lands on when castling to this side, in Chess960 as in chess.
+    pub fn king_to_file(self) -> u64 {
+        match self {
+            CastlingRights::QueenSide => 2,
+            CastlingRights::KingSide => 6,
+        }
+    }
+
+    /// The file the rook lands on when castling to this side.
+    pub fn rook_to_file(self) -> u64 {
+        match self {
+            CastlingRights::QueenSide => 3,
+            CastlingRights::KingSide => 5,
+        }
+    }
+}
+
+/// The rank a side's king and rooks start on.
+pub(crate) fn back_rank(colour: Colour) -> u64 {
+    match colour {
+        Colour::White => 0,
+        Colour::Black => 7,
+    }
+}
+
+pub(crate) fn square(file: u64, rank: u64) -> Square {
+    Square::try_from(rank * 8 + file).unwrap()
+}
+
+impl Board {
+    /// The square of the rook a side may still castle with, if it may. In Chess960 this
+    /// can be any square of the back rank.
+    pub fn castling_rook(&self, colour: Colour, side: CastlingRights) -> Option<Square> {
+        self.castling_rights[colour as usize][side as usize].map(|file| square(u64::from(file), back_rank(colour)))
+    }
+
+    /// The rook's from- and to-square for a castling move generated in this position.
+    pub(crate) fn castling_rook_squares(&self, mv: Move) -> (Square, Square) {
+        let colour = if mv.from().rank() == 0 { Colour::White } else { Colour::Black };
+        let side = if mv.is_castle_kingside() { CastlingRights::KingSide } else { CastlingRights::QueenSide };
+        let rook_from = self.castling_rook(colour, side).expect("castling without the right to");
+        (rook_from, square(side.rook_to_file(), back_rank(colour)))
+    }
+}
@@ -0,0 +1,105 @@
+use crate::board::Board;
+
+/// The number of Chess960 starting positions.
+pub const POSITIONS: u16 = 960;
+
+/// The index of the standard starting position, RNBQKBNR.
+pub const STANDARD_INDEX: u16 = 518;
+
+/// The placements of the two knights among the five squares left after the bishops and
+/// the queen, in Scharnagl's order.
+const KNIGHTS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+/// The white back rank of Chess960 starting position `index` (0 to 959) in Scharnagl's
+/// numbering, e.g. `RNBQKBNR` for 518.
+pub fn back_rank(index: u16) -> Option<[char; 8]> {
+    if index >= POSITIONS {
+        return None;
+    }
+    let mut n = index as usize;
+    let mut rank = [' '; 8];
+
+    // Light-squared bishop on b, d, f or h, then the dark-squared one on a, c, e or g
+    rank[2 * (n % 4) + 1] = 'B';
+    n /= 4;
+    rank[2 * (n % 4)] = 'B';
+    n /= 4;
+
+    let place = |rank: &mut [char; 8], nth: usize, piece: char| {
+        let file = (0..8).filter(|f| rank[*f] == ' ').nth(nth).unwrap();
+        rank[file] = piece;
+    };
+    place(&mut rank, n % 6, 'Q');
+    n /= 6;
+
+    // Knights go in from the right so that the first one's index is not disturbed
+    let (first, second) = KNIGHTS[n];
+    place(&mut rank, second, 'N');
+    place(&mut rank, first, 'N');
+
+    // The king between the rooks on the three squares left
+    for piece in ['R', 'K', 'R'].iter() {
+        place(&mut rank, 0, *piece);
+    }
+    Some(rank)
+}
+
+/// The FEN of Chess960 starting position `index`, with Shredder-FEN castling rights.
+pub fn start_fen(index: u16) -> Option<String> {
+    let rank = back_rank(index)?;
+    let white: String = rank.iter().collect();
+    let black = white.to_ascii_lowercase();
+    let rooks: Vec<char> = rank.iter()
+        .enumerate()
+        .filter(|(_, c)| **c == 'R')
+        .map(|(file, _)| (b'a' + file as u8) as char)
+        .rev()
+        .collect();
+    let castling: String = rooks.iter().map(char::to_ascii_uppercase).chain(rooks.iter().copied()).collect();
+
+    Some(format!("{}/pppppppp/8/8/8/8/PPPPPPPP/{} w {} - 0 1", black, white, castling))
+}
+
+/// Chess960 starting position `index` (0 to 959).
+pub fn start_position(index: u16) -> Option<Board> {
+    Some(start_fen(index)?.parse().expect("generated an invalid FEN"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::board::chess960::{back_rank, start_fen, start_position, POSITIONS, STANDARD_INDEX};
+    use crate::board::fen::STARTING_FEN;
+    use crate::board::moves::MoveList;
+
+    #[test]
+    fn chess960_known_positions() {
+        let rank = |index| back_rank(index).unwrap().iter().collect::<String>();
+        assert_eq!(rank(0), "BBQNNRKR");
+        assert_eq!(rank(STANDARD_INDEX), "RNBQKBNR");
+        assert_eq!(rank(959), "RKRNNQBB");
+        assert_eq!(start_position(STANDARD_INDEX).unwrap().to_string(), STARTING_FEN);
+        assert_eq!(start_fen(0).unwrap(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1");
+        assert!(back_rank(POSITIONS).is_none());
+    }
+
+    #[test]
+    fn chess960_all_positions() {
+        let mut seen = HashSet::new();
+        for index in 0..POSITIONS {
+            let rank = back_rank(index).unwrap();
+            assert!(seen.insert(rank), "{} repeats", index);
+
+            let files = |piece| (0..8).filter(|f| rank[*f] == piece).collect::<Vec<_>>();
+            let (bishops, rooks, king) = (files('B'), files('R'), files('K'));
+            assert_eq!(bishops.len(), 2);
+            assert_ne!(bishops[0] % 2, bishops[1] % 2);
+            assert!(rooks[0] < king[0] && king[0] < rooks[1]);
+
+            let board = start_position(index).unwrap();
+            let mut moves = MoveList::new();
+            board.generate_legal(&mut moves);
+            assert!(moves.len() >= 18, "{}", index);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+use crate::board::Board;
+use crate::board::fen;
+use crate::board::moves::Move;
+use crate::board::san::SanError;
+
+#[derive(thiserror::Error, Debug)]
+pub enum EpdError {
+    #[error("invalid number of fields: {0}, expected at least 4")]
+    Size(usize),
+    #[error("invalid position: {0}")]
+    Position(#[from] fen::ParseError),
+    #[error("unterminated string in operation {0}")]
+    Unterminated(String),
+    #[error("invalid operand for {opcode}: {operand}")]
+    Operand { opcode: String, operand: String },
+    #[error("invalid move: {0}")]
+    San(#[from] SanError),
+}
+
+/// One EPD operation: an opcode such as `bm` or `id` and its operands.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Operation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+/// An Extended Position Description: the first four fields of a FEN followed by
+/// semicolon-terminated operations, e.g. `... w - - bm Qd1+; id "WAC.001";`.
+///
+/// The `hmvc` and `fmvn` operations, when present, set the board's move counters.
+#[derive(Clone)]
+pub struct Epd {
+    pub board: Board,
+    pub operations: Vec<Operation>,
+}
+
+impl Epd {
+    /// The operands of the first operation with this opcode.
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations.iter().find(|op| op.opcode == opcode).map(|op| op.operands.as_slice())
+    }
+
+    /// Sets the operands of an operation, adding it after the others if it is new.
+    pub fn set_operation(&mut self, opcode: &str, operands: Vec<String>) {
+        match self.operations.iter_mut().find(|op| op.opcode == opcode) {
+            Some(op) => op.operands = operands,
+            None => self.operations.push(Operation { opcode: opcode.to_string(), operands }),
+        }
+    }
+
+    /// The position's name, from the `id` operation.
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id").and_then(|ops| ops.first()).map(String::as_str)
+    }
+
+    /// The best moves given by the `bm` operation, empty if there is none.
+    pub fn best_moves(&self) -> Result<Vec<Move>, EpdError> {
+        self.moves("bm")
+    }
+
+    /// The moves to avoid given by the `am` operation, empty if there is none.
+    pub fn avoid_moves(&self) -> Result<Vec<Move>, EpdError> {
+        self.moves("am")
+    }
+
+    fn moves(&self, opcode: &str) -> Result<Vec<Move>, EpdError> {
+        self.operation(opcode)
+            .unwrap_or(&[])
+            .iter()
+            .map(|san| Ok(self.board.parse_san(san)?))
+            .collect()
+    }
+}
+
+impl FromStr for Epd {
+    type Err = EpdError;
+
+    fn from_str(s: &str) -> Result<Self, EpdError> {
+        // The position fields are separated by single spaces, as in FEN
+        let mut rest = s.trim();
+        let mut fields = Vec::with_capacity(4);
+        while fields.len() < 4 && !rest.is_empty() {
+            let end = rest.find(' ').unwrap_or(rest.len());
+            fields.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+        if fields.len() != 4 {
+            return Err(EpdError::Size(fields.len()));
+        }
+        let mut board = Board::from_position_fields(&fields)?;
+
+        let operations = parse_operations(rest)?;
+        for op in operations.iter() {
+            let counter = match op.opcode.as_str() {
+                "hmvc" | "fmvn" => op.operands.first(),
+                _ => continue,
+            };
+            let invalid = || EpdError::Operand { opcode: op.opcode.clone(), operand: op.operands.join(" ") };
+            let value = counter.ok_or_else(invalid)?;
+            if op.opcode == "hmvc" {
+                board.half_moves = value.parse().map_err(|_| invalid())?;
+            }
+            else {
+                board.full_moves = value.parse().map_err(|_| invalid())?;
+            }
+        }
+
+        Ok(Self { board, operations })
+    }
+}
+
+/// Reads the operations after the position. The final semicolon may be missing.
+fn parse_operations(s: &str) -> Result<Vec<Operation>, EpdError> {
+    let mut operations = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(operations);
+        }
+
+        let mut op = Operation { opcode: String::new(), operands: Vec::new() };
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ';') {
+            op.opcode.push(c);
+        }
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.next() {
+                None | Some(';') => break,
+                Some('"') => {
+                    let mut operand = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(c) => operand.push(c),
+                            None => return Err(EpdError::Unterminated(op.opcode)),
+                        }
+                    }
+                    op.operands.push(operand);
+                }
+                Some(c) => {
+                    let mut operand = c.to_string();
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ';') {
+                        operand.push(c);
+                    }
+                    op.operands.push(operand);
+                }
+            }
+        }
+
+        if !op.opcode.is_empty() {
+            operations.push(op);
+        }
+    }
+}
+
+/// Comment and identification operations hold free text, so they are always quoted.
+fn is_string_opcode(opcode: &str) -> bool {
+    opcode == "id" || (opcode.len() == 2 && opcode.starts_with('c') && opcode.as_bytes()[1].is_ascii_digit())
+}
+
+impl fmt::Display for Epd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let fen = self.board.to_string();
+        let position: Vec<&str> = fen.split(' ').take(4).collect();
+        write!(f, "{}", position.join(" "))?;
+
+        for op in self.operations.iter() {
+            write!(f, " {}", op.opcode)?;
+            for operand in op.operands.iter() {
+                if is_string_opcode(&op.opcode) || operand.is_empty() || operand.contains(|c: char| c.is_whitespace() || c == ';') {
+                    write!(f, " \"{}\"", operand)?;
+                }
+                else {
+                    write!(f, " {}", operand)?;
+                }
+            }
+            write!(f, ";")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::epd::{Epd, EpdError};
+
+    const WAC_1: &str = r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";"#;
+
+    #[test]
+    fn epd_parse() {
+        let epd: Epd = WAC_1.parse().unwrap();
+        assert_eq!(epd.id(), Some("WAC.001"));
+        assert_eq!(epd.operation("bm").unwrap(), ["Qg6"]);
+        assert_eq!(epd.operations.len(), 2);
+        assert_eq!(epd.best_moves().unwrap().iter().map(|m| m.to_string()).collect::<Vec<_>>(), ["g3g6"]);
+        assert!(epd.avoid_moves().unwrap().is_empty());
+        assert_eq!(epd.board.to_string(), "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1");
+    }
+
+    #[test]
+    fn epd_operations() {
+        let epd: Epd = r#"4k3/8/8/8/8/8/8/4K2R w K - bm O-O Rh8+; c0 "two; moves"; acd 12; ce -35; hmvc 7; fmvn 40; noop"#
+            .parse()
+            .unwrap();
+        assert_eq!(epd.best_moves().unwrap().len(), 2);
+        assert_eq!(epd.operation("c0").unwrap(), ["two; moves"]);
+        assert_eq!(epd.operation("ce").unwrap(), ["-35"]);
+        assert!(epd.operation("noop").unwrap().is_empty());
+        assert_eq!(epd.board.to_string(), "4k3/8/8/8/8/8/8/4K2R w K - 7 40");
+    }
+
+    #[test]
+    fn epd_round_trip() {
+        let mut epd: Epd = WAC_1.parse().unwrap();
+        assert_eq!(epd.to_string(), WAC_1);
+
+        epd.set_operation("acd", vec!["10".to_string()]);
+        epd.set_operation("c0", vec!["solved by dogfish".to_string()]);
+        let written = epd.to_string();
+        assert!(written.ends_with(r#"id "WAC.001"; acd 10; c0 "solved by dogfish";"#));
+        assert_eq!(written.parse::<Epd>().unwrap().operations, epd.operations);
+    }
+
+    #[test]
+    fn epd_errors() {
+        assert!(matches!("8/8/8/8 w".parse::<Epd>(), Err(EpdError::Size(2))));
+        assert!(matches!("8/8/8/8/8/8/8/8 x - - bm e4;".parse::<Epd>(), Err(EpdError::Position(_))));
+        assert!(matches!(r#"4k3/8/8/8/8/8/8/4K3 w - - id "open"#.parse::<Epd>(), Err(EpdError::Unterminated(_))));
+        assert!(matches!("4k3/8/8/8/8/8/8/4K3 w - - hmvc x;".parse::<Epd>(), Err(EpdError::Operand { .. })));
+        let epd: Epd = "4k3/8/8/8/8/8/8/4K3 w - - bm Qh5;".parse().unwrap();
+        assert!(matches!(epd.best_moves(), Err(EpdError::San(_))));
+    }
+}
@@ -1,6 +1,6 @@
 use std::str::FromStr;
 use crate::board::{Board};
-use crate::board::castling::CastlingRights;
+use crate::board::castling::{back_rank, square, CastlingRights};
 use crate::board_representation::square::Square;
 use std::convert::TryInto;
 use crate::piece::colour::Colour;
@@ -8,6 +8,10 @@ use crate::piece::piecetype::PieceType;
 use crate::board_representation;
 use crate::board::fen::ParseError::{Size, Rank, PiecePosition, Unrecognised, Castling, EnPassant};
 use std::num::ParseIntError;
+use std::fmt;
+use std::fmt::Formatter;
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
 #[derive(thiserror::Error, Debug)]
 pub enum ParseError {
@@ -36,14 +40,34 @@ impl FromStr for Board {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, ParseError> {
-        let mut board = Board::new();
-
         // Check if there are enough parts for the FEN
         let v: Vec<&str> = s.trim().split(' ').collect();
         if v.len() != 6 {
             return Err(Size(v.len()));
         }
 
+        let mut board = Board::from_position_fields(&v[..4])?;
+
+        // Half moves and full moves
+        board.half_moves = v[4].parse()?;
+        board.full_moves = v[5].parse()?;
+
+        Ok(board)
+    }
+}
+
+impl Board {
+    /// Parses the four FEN fields that describe the position itself (placement, side to
+    /// move, castling and en passant), which EPD shares. The move counters are left at
+    /// 0 and 1.
+    pub(crate) fn from_position_fields(v: &[&str]) -> Result<Self, ParseError> {
+        let mut board = Board::new();
+        board.full_moves = 1;
+
+        if v.len() != 4 {
+            return Err(Size(v.len()));
+        }
+
         // Check if there are 8 ranks to use
         let pieces: Vec<&str> = v[0].trim().split('/').collect();
         if pieces.len() != 8 {
@@ -143,59 +167,30 @@ impl FromStr for Board {
             }
         };
 
-        // Castling rights
-        for char in v[2].chars() {
-            match char {
-                'K' => {
-                    board.castling_rights[Colour::White as usize][CastlingRights::KingSide as usize] = true;
-                },
-                'Q' => {
-                    board.castling_rights[Colour::White as usize][CastlingRights::QueenSide as usize] = true;
-                },
-                'k' => {
-                    board.castling_rights[Colour::Black as usize][CastlingRights::KingSide as usize] = true;
-                },
-                'q' => {
-                    board.castling_rights[Colour::Black as usize][CastlingRights::QueenSide as usize] = true;
-                }
-                _ => {
-                    return Err(Unrecognised(char.to_string()))
-                }
-            }
-        }
-
-        // Validate said castling rights
-        let white_castling_squares = [0_u64, 7];
-        for wc in board.castling_rights[Colour::White as usize].iter().zip(white_castling_squares.iter()) {
-            if *wc.0 {
-                match board.mailbox.get_piece((*wc.1).try_into()?) {
-                    None => {
-                        return Err(Castling)
-                    }
-                    Some(p) if (p.piece_type() != PieceType::R || p.colour() != Colour::White)=> {
-                        return Err(Castling)
-                    }
-                    _ => {}
-                }
-            }
-        }
-
-        // Black
-        let black_castling_squares = [56_u64, 63];
-        for bc in board.castling_rights[Colour::Black as usize].iter().zip(black_castling_squares.iter()) {
-            if *bc.0 {
-                match board.mailbox.get_piece((*bc.1).try_into()?) {
-                    None => {
-                        return Err(Castling)
-                    }
-                    Some(p)
-                    if (p.piece_type() != PieceType::R ||
-                        p.colour() != Colour::Black) => {
-
-                        return Err(Castling)
-                    }
-                    _ => {}
-                }
+        // Castling rights, "-" if neither side can castle. KQkq name the outermost rook on
+        // each side of the king (X-FEN); for Chess960 the rook's file may be given instead
+        // (Shredder-FEN, e.g. HAha), upper case for white
+        if v[2] != "-" {
+            for char in v[2].chars() {
+                let colour = if char.is_ascii_uppercase() { Colour::White } else { Colour::Black };
+                let rank = back_rank(colour);
+                let files = |piece_type: PieceType| -> Vec<u64> {
+                    (0..8_u64)
+                        .filter(|file| board.piece_on(square(*file, rank)) == Some((colour, piece_type).into()))
+                        .collect()
+                };
+
+                let king = *files(PieceType::K).first().ok_or(Castling)?;
+                let rooks = files(PieceType::R);
+                let rook = match char.to_ascii_lowercase() {
+                    'k' => rooks.iter().copied().filter(|file| *file > king).max(),
+                    'q' => rooks.iter().copied().filter(|file| *file < king).min(),
+                    c @ 'a'..='h' => rooks.iter().copied().find(|file| *file == c as u64 - 'a' as u64),
+                    _ => return Err(Unrecognised(char.to_string())),
+                };
+                let rook = rook.ok_or(Castling)?;
+                let side = if rook > king { CastlingRights::KingSide } else { CastlingRights::QueenSide };
+                board.castling_rights[colour as usize][side as usize] = Some(rook as u8);
             }
         }
 
@@ -217,30 +212,122 @@ impl FromStr for Board {
             }
         };
 
-        // Half moves and full moves
-        board.half_moves = v[4].parse()?;
-        board.full_moves = v[5].parse()?;
+        board.hash = board.compute_hash_from_scratch();
+        board.pawn_hash = board.compute_pawn_hash_from_scratch();
 
         Ok(board)
     }
 }
 
-impl ToString for Board {
-    fn to_string(&self) -> String {
-        unimplemented!() // TODO: Write a Board to FEN converter
+impl Board {
+    /// The position as Shredder-FEN, which names each castling rook by its file (`HAha` in
+    /// the standard starting position) where `Display` writes X-FEN.
+    pub fn to_shredder_fen(&self) -> String {
+        let fen = self.to_string();
+        let castling = self.castling_field(true);
+        let mut fields: Vec<&str> = fen.split(' ').collect();
+        fields[2] = &castling;
+        fields.join(" ")
+    }
+
+    /// The castling field of a FEN. X-FEN writes KQkq for a rook that is the outermost on
+    /// its side of the king and the rook's file otherwise; Shredder-FEN always the file.
+    fn castling_field(&self, shredder: bool) -> String {
+        let rights = [
+            (Colour::White, CastlingRights::KingSide),
+            (Colour::White, CastlingRights::QueenSide),
+            (Colour::Black, CastlingRights::KingSide),
+            (Colour::Black, CastlingRights::QueenSide),
+        ];
+
+        let mut field = String::new();
+        for (colour, side) in rights.iter() {
+            let rook = match self.castling_rook(*colour, *side) {
+                Some(rook) => rook,
+                None => continue,
+            };
+            let outermost = (0..8_u64)
+                .filter(|file| match side {
+                    CastlingRights::KingSide => *file > rook.file(),
+                    CastlingRights::QueenSide => *file < rook.file(),
+                })
+                .all(|file| self.piece_on(square(file, rook.rank())) != Some((*colour, PieceType::R).into()));
+
+            let c = match side {
+                _ if shredder || !outermost => (b'a' + rook.file() as u8) as char,
+                CastlingRights::KingSide => 'k',
+                CastlingRights::QueenSide => 'q',
+            };
+            field.push(if *colour == Colour::White { c.to_ascii_uppercase() } else { c });
+        }
+
+        if field.is_empty() {
+            field.push('-');
+        }
+        field
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Pieces, from the eighth rank down
+        for rank in (0..8_u64).rev() {
+            let mut empty = 0;
+            for file in 0..8_u64 {
+                match self.mailbox.get_piece((rank * 8 + file).try_into().unwrap()) {
+                    Some(p) => {
+                        if empty > 0 {
+                            write!(f, "{}", empty)?;
+                            empty = 0;
+                        }
+                        write!(f, "{}", p)?;
+                    }
+                    None => {
+                        empty += 1;
+                    }
+                }
+            }
+            if empty > 0 {
+                write!(f, "{}", empty)?;
+            }
+            if rank > 0 {
+                write!(f, "/")?;
+            }
+        }
+
+        // Turn
+        match self.player {
+            Colour::White => write!(f, " w ")?,
+            Colour::Black => write!(f, " b ")?,
+        }
+
+        // Castling rights
+        write!(f, "{}", self.castling_field(false))?;
+
+        // En passant square
+        match self.en_passant() {
+            Some(square) => write!(f, " {}", square)?,
+            None => write!(f, " -")?,
+        }
+
+        // Half moves and full moves
+        write!(f, " {} {}", self.half_moves, self.full_moves)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::board::Board;
+    use crate::board::castling::CastlingRights;
+    use crate::board::fen::STARTING_FEN;
+    use crate::board::moves::MoveList;
     use crate::piece::colour::Colour;
 
     #[test]
-    #[should_panic()]
+    #[should_panic]
     fn fen_parse_non_fen() {
         let fen_str = "cat dog meow woof";
-        let board: Board = fen_str.parse().unwrap();
+        let _board: Board = fen_str.parse().unwrap();
     }
 
     #[test]
@@ -264,8 +351,9 @@ mod tests {
     #[test]
     #[should_panic(expected = "Castling")]
     fn fen_parse_invalid_castling_black_kingside() {
-        let fen_str = "r3k1r1/pp3ppp/2pp1nb1/q2Pp3/P3P3/2N5/1PP2PPP/R3K2R w KQkq e6 0 1";
-        let board: Board = fen_str.parse().unwrap();
+        // No black rook on the king side at all
+        let fen_str = "r3kb2/pp3ppp/2pp1nb1/q2Pp3/P3P3/2N5/1PP2PPP/R3K2R w KQkq e6 0 1";
+        let _board: Board = fen_str.parse().unwrap();
     }
 
     #[test]
@@ -273,6 +361,60 @@ mod tests {
     fn fen_parse_invalid_spacing() {
         // Notice the 2pp2nb1
         let fen_str = "r3k1r1/pp3ppp/2pp2nb1/q2Pp3/P3P3/2N5/1PP2PPP/R3K2R w KQq e6 0 1";
-        let board: Board = fen_str.parse().unwrap();
+        let _board: Board = fen_str.parse().unwrap();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn fen_round_trip() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "r3k1r1/pp3ppp/2pp1nb1/q2Pp3/P3P3/2N5/1PP2PPP/R3K2R w KQq e6 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "8/8/8/8/8/8/8/K6k b - - 99 312",
+            "4k3/8/8/8/8/8/8/4K2R w K - 12 40",
+        ];
+
+        for fen in fens.iter() {
+            let board: Board = fen.parse().unwrap();
+            assert_eq!(board.to_string(), *fen);
+        }
+    }
+
+    #[test]
+    fn fen_chess960_castling() {
+        // Shredder-FEN names the rooks by file; X-FEN only where KQkq would be ambiguous
+        let board: Board = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9".parse().unwrap();
+        assert_eq!(board.castling_rook(Colour::White, CastlingRights::QueenSide).unwrap().to_string(), "f1");
+        assert_eq!(board.castling_rook(Colour::Black, CastlingRights::KingSide).unwrap().to_string(), "h8");
+        assert_eq!(board.to_string(), "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+        assert_eq!(board.to_shredder_fen(), "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+
+        let xfen: Board = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9".parse().unwrap();
+        assert_eq!(xfen.to_shredder_fen(), board.to_shredder_fen());
+
+        // The inner of two king side rooks
+        let inner: Board = "4k3/8/8/8/8/8/8/1K1R3R w D - 0 1".parse().unwrap();
+        assert_eq!(inner.castling_rook(Colour::White, CastlingRights::KingSide).unwrap().to_string(), "d1");
+        assert_eq!(inner.to_string(), "4k3/8/8/8/8/8/8/1K1R3R w D - 0 1");
+        assert_eq!(STARTING_FEN.parse::<Board>().unwrap().to_shredder_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1");
+
+        assert!("4k3/8/8/8/8/8/8/1K1R3R w B - 0 1".parse::<Board>().is_err());
+        assert!("4k3/8/8/8/8/8/8/1K1R3R w C - 0 1".parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn fen_after_moves() {
+        let mut board: Board = STARTING_FEN.parse().unwrap();
+        let mut moves = MoveList::new();
+        board.generate_legal(&mut moves);
+        let e4 = *moves.iter().find(|m| m.to_string() == "e2e4").unwrap();
+
+        board.make_move(e4);
+        assert_eq!(board.to_string(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    }
+}
@@ -0,0 +1,317 @@
+use std::convert::TryFrom;
+use crate::board::Board;
+use crate::board::castling::back_rank;
+use crate::board::moves::Move;
+use crate::board::zobrist::ZOBRIST_KEYS;
+use crate::board::PLAYERS_COUNT;
+use crate::board_representation::bitboard::BitBoard;
+use crate::board_representation::bitboard::shift::Direction;
+use crate::board_representation::square::Square;
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+use crate::piece::Piece;
+
+/// State that cannot be recovered from a `Move` alone, returned by `Board::make_move` and
+/// handed back to `Board::unmake_move`.
+#[derive(Copy, Clone, Debug)]
+pub struct Undo {
+    captured: Option<Piece>,
+    castling_rights: [[Option<u8>; 2]; PLAYERS_COUNT],
+    en_passant: BitBoard,
+    half_moves: u8,
+    hash: u64,
+    pawn_hash: u64,
+}
+
+impl Undo {
+    pub fn captured(self) -> Option<Piece> {
+        self.captured
+    }
+}
+
+impl Board {
+    /// Plays a move generated for this position, updating the bitboards, the mailbox and
+    /// all of the game state.
+    pub fn make_move(&mut self, mv: Move) -> Undo {
+        let us = self.player;
+        let from = mv.from();
+        let to = mv.to();
+        let piece = self.mailbox.get_piece(from).expect("no piece on the from square");
+
+        let mut undo = Undo {
+            captured: None,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            half_moves: self.half_moves,
+            hash: self.hash,
+            pawn_hash: self.pawn_hash,
+        };
+
+        self.history.push(self.hash);
+
+        // Castling rights and en passant are XORed out here and back in once updated;
+        // the piece keys are kept up to date by `set_piece` and `remove_square`.
+        self.hash ^= ZOBRIST_KEYS.castling_rights(&self.castling_rights) ^ self.en_passant_key();
+
+        // Captures
+        if mv.is_en_passant() {
+            let captured = to.shift(backward(us));
+            undo.captured = self.mailbox.get_piece(captured);
+            self.remove_square(captured);
+        }
+        else if mv.is_capture() {
+            undo.captured = self.mailbox.get_piece(to);
+            self.remove_square(to);
+        }
+
+        if mv.is_castle() {
+            // In Chess960 the king and rook may land on each other's squares, so the rook
+            // is lifted off the board first
+            let (rook_from, rook_to) = self.castling_rook_squares(mv);
+            self.remove_square(rook_from);
+            self.move_square(from, to);
+            self.set_piece(rook_to, (us, PieceType::R).into());
+        }
+        else {
+            self.move_square(from, to);
+        }
+
+        if let Some(piece_type) = mv.promotion() {
+            self.remove_square(to);
+            self.set_piece(to, (us, piece_type).into());
+        }
+
+        // Moving the king, or moving from or onto a castling rook's square, loses the
+        // matching rights
+        if piece.piece_type() == PieceType::K {
+            self.castling_rights[us as usize] = [None; 2];
+        }
+        self.clear_castling_rights(from);
+        self.clear_castling_rights(to);
+
+        self.en_passant = if mv.is_double_push() {
+            to.shift(backward(us)).into()
+        }
+        else {
+            0.into()
+        };
+
+        if piece.piece_type() == PieceType::P || undo.captured.is_some() {
+            self.half_moves = 0;
+        }
+        else {
+            self.half_moves = self.half_moves.saturating_add(1);
+        }
+        if us == Colour::Black {
+            self.full_moves += 1;
+        }
+        self.player = us.opposite();
+
+        self.hash ^= ZOBRIST_KEYS.side
+            ^ ZOBRIST_KEYS.castling_rights(&self.castling_rights)
+            ^ self.en_passant_key();
+
+        debug_assert!(self.is_consistent(), "board out of sync after {}", mv);
+        debug_assert_eq!(self.hash, self.compute_hash_from_scratch(), "hash drifted after {}", mv);
+        debug_assert_eq!(self.pawn_hash, self.compute_pawn_hash_from_scratch(), "pawn hash drifted after {}", mv);
+        undo
+    }
+
+    /// Takes back a move played with `make_move`, given the `Undo` it returned.
+    pub fn unmake_move(&mut self, mv: Move, undo: Undo) {
+        let us = self.player.opposite();
+        let from = mv.from();
+        let to = mv.to();
+
+        if mv.is_promotion() {
+            self.remove_square(to);
+            self.set_piece(to, (us, PieceType::P).into());
+        }
+
+        // The rights are needed to find the castling rook
+        self.castling_rights = undo.castling_rights;
+
+        if mv.is_castle() {
+            let (rook_from, rook_to) = self.castling_rook_squares(mv);
+            self.remove_square(rook_to);
+            self.move_square(to, from);
+            self.set_piece(rook_from, (us, PieceType::R).into());
+        }
+        else {
+            self.move_square(to, from);
+        }
+
+        if let Some(captured) = undo.captured {
+            let square = if mv.is_en_passant() {
+                to.shift(backward(us))
+            }
+            else {
+                to
+            };
+            self.set_piece(square, captured);
+        }
+
+        self.en_passant = undo.en_passant;
+        self.half_moves = undo.half_moves;
+        self.hash = undo.hash;
+        self.history.pop();
+        self.pawn_hash = undo.pawn_hash;
+        if us == Colour::Black {
+            self.full_moves -= 1;
+        }
+        self.player = us;
+
+        debug_assert!(self.is_consistent(), "board out of sync after undoing {}", mv);
+        debug_assert_eq!(self.hash, self.compute_hash_from_scratch(), "hash drifted after undoing {}", mv);
+        debug_assert_eq!(self.pawn_hash, self.compute_pawn_hash_from_scratch(), "pawn hash drifted after undoing {}", mv);
+    }
+
+    fn clear_castling_rights(&mut self, square: Square) {
+        for colour in [Colour::White, Colour::Black].iter() {
+            if square.rank() != back_rank(*colour) {
+                continue;
+            }
+            for rook in self.castling_rights[*colour as usize].iter_mut() {
+                if *rook == Some(square.file() as u8) {
+                    *rook = None;
+                }
+            }
+        }
+    }
+
+    /// Whether the bitboards and the mailbox describe the same position.
+    fn is_consistent(&self) -> bool {
+        let white = self.bb_player[Colour::White as usize];
+        let black = self.bb_player[Colour::Black as usize];
+        if !(white & black).is_empty() {
+            return false;
+        }
+
+        let pieces = self.bb_pieces.iter().fold(BitBoard::new(0), |acc, bb| acc | *bb);
+        if pieces != (white | black) || self.bb_pieces.iter().map(|bb| bb.count()).sum::<u32>() != pieces.count() {
+            return false;
+        }
+
+        (0..64_u64).all(|idx| {
+            let square = Square::try_from(idx).unwrap();
+            let bb = BitBoard::from(square);
+            match self.mailbox.get_piece(square) {
+                None => (pieces & bb).is_empty(),
+                Some(p) => {
+                    !(self.bb_player[p.colour() as usize] & bb).is_empty()
+                        && !(self.bb_pieces[p.piece_type() as usize] & bb).is_empty()
+                }
+            }
+        })
+    }
+}
+
+/// The direction pointing back towards `colour`'s own side of the board.
+fn backward(colour: Colour) -> Direction {
+    match colour {
+        Colour::White => Direction::South,
+        Colour::Black => Direction::North,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use crate::board::Board;
+    use crate::board::castling::CastlingRights;
+    use crate::board::moves::MoveList;
+    use crate::board_representation::square::Square;
+    use crate::piece::colour::Colour;
+    use crate::piece::piecetype::PieceType;
+    use crate::piece::Piece;
+    use crate::testing::find_move;
+
+    fn piece_on(board: &Board, square: (char, char)) -> Option<Piece> {
+        board.piece_on(Square::try_from(square).unwrap())
+    }
+
+    #[test]
+    fn make_unmake_restores_position() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1",
+        ];
+
+        for fen in fens.iter() {
+            let mut board: Board = fen.parse().unwrap();
+            let mut moves = MoveList::new();
+            board.generate_legal(&mut moves);
+
+            for mv in moves.iter() {
+                let pieces = board.bb_pieces;
+                let players = board.bb_player;
+                let castling = board.castling_rights;
+                let en_passant = board.en_passant;
+                let clocks = (board.half_moves, board.full_moves);
+
+                let undo = board.make_move(*mv);
+                assert_eq!(board.player, Colour::Black, "{} {}", fen, mv);
+                board.unmake_move(*mv, undo);
+
+                assert!(board.bb_pieces == pieces, "{} {}", fen, mv);
+                assert!(board.bb_player == players, "{} {}", fen, mv);
+                assert!(board.en_passant == en_passant, "{} {}", fen, mv);
+                assert_eq!(board.castling_rights, castling, "{} {}", fen, mv);
+                assert_eq!((board.half_moves, board.full_moves), clocks, "{} {}", fen, mv);
+                assert_eq!(board.player, Colour::White);
+            }
+        }
+    }
+
+    #[test]
+    fn make_castling() {
+        let mut board: Board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 3 10".parse().unwrap();
+
+        board.make_move(find_move(&board, "e1g1"));
+        assert_eq!(piece_on(&board, ('g', '1')), Some((Colour::White, PieceType::K).into()));
+        assert_eq!(piece_on(&board, ('f', '1')), Some((Colour::White, PieceType::R).into()));
+        assert_eq!(piece_on(&board, ('h', '1')), None);
+        assert!(!board.castling_rights(Colour::White, CastlingRights::KingSide));
+        assert!(!board.castling_rights(Colour::White, CastlingRights::QueenSide));
+        assert_eq!(board.half_moves, 4);
+
+        board.make_move(find_move(&board, "e8c8"));
+        assert_eq!(piece_on(&board, ('c', '8')), Some((Colour::Black, PieceType::K).into()));
+        assert_eq!(piece_on(&board, ('d', '8')), Some((Colour::Black, PieceType::R).into()));
+        assert_eq!(piece_on(&board, ('a', '8')), None);
+        assert_eq!(board.full_moves, 11);
+    }
+
+    #[test]
+    fn make_en_passant_and_double_push() {
+        let mut board: Board = "4k3/2p5/8/3P4/8/8/8/4K3 b - - 0 1".parse().unwrap();
+
+        board.make_move(find_move(&board, "c7c5"));
+        assert_eq!(board.en_passant(), Some(Square::try_from(('c', '6')).unwrap()));
+
+        let undo = board.make_move(find_move(&board, "d5c6"));
+        assert_eq!(undo.captured(), Some((Colour::Black, PieceType::P).into()));
+        assert_eq!(piece_on(&board, ('c', '5')), None);
+        assert_eq!(piece_on(&board, ('c', '6')), Some((Colour::White, PieceType::P).into()));
+        assert_eq!(board.en_passant(), None);
+    }
+
+    #[test]
+    fn make_promotion_capture_removes_castling() {
+        let mut board: Board = "r3k2r/1P6/8/8/8/8/8/4K3 w kq - 0 1".parse().unwrap();
+
+        let mv = find_move(&board, "b7a8q");
+        let undo = board.make_move(mv);
+        assert_eq!(piece_on(&board, ('a', '8')), Some((Colour::White, PieceType::Q).into()));
+        assert!(!board.castling_rights(Colour::Black, CastlingRights::QueenSide));
+        assert!(board.castling_rights(Colour::Black, CastlingRights::KingSide));
+
+        board.unmake_move(mv, undo);
+        assert_eq!(piece_on(&board, ('a', '8')), Some((Colour::Black, PieceType::R).into()));
+        assert_eq!(piece_on(&board, ('b', '7')), Some((Colour::White, PieceType::P).into()));
+        assert!(board.castling_rights(Colour::Black, CastlingRights::QueenSide));
+    }
+}
@@ -1,12 +1,25 @@
-use std::sync::Arc;
+use std::convert::TryInto;
 use crate::board_representation::bitboard::BitBoard;
 use crate::board_representation::mailbox::Mailbox;
 use crate::board_representation::square::Square;
 use crate::piece::colour::Colour;
 use crate::piece::Piece;
+use crate::piece::piecetype::PieceType;
+use crate::board::castling::CastlingRights;
+use crate::board::zobrist::ZOBRIST_KEYS;
 
+pub mod epd;
 pub mod fen;
 pub mod castling;
+pub mod chess960;
+pub mod moves;
+pub mod movegen;
+pub mod make_move;
+pub mod zobrist;
+pub mod see;
+pub mod attacks;
+pub mod outcome;
+pub mod san;
 
 pub const PLAYERS_COUNT: usize = 2; // Number of players
 pub const PIECES_TYPE_COUNT: usize = 6; // Number of types of pieces there are for each side
@@ -15,6 +28,7 @@ pub const SQUARES: usize = 64;
 pub const FILES: usize = 8;
 pub const RANKS: usize = 8;
 
+#[derive(Clone)]
 pub struct Board {
     player: Colour,
 
@@ -23,13 +37,21 @@ pub struct Board {
 
     mailbox: Mailbox,
 
-    castling_rights: [[bool; 2]; PLAYERS_COUNT],
+    /// The file of the rook each side may still castle with, indexed by colour and then by
+    /// `CastlingRights`. Files rather than flags, so that Chess960 rooks can start anywhere
+    castling_rights: [[Option<u8>; 2]; PLAYERS_COUNT],
     en_passant: BitBoard,
 
     half_moves: u8,
-    full_moves: u8,
+    full_moves: u16,
 
-    previous: Option<Arc<Board>>
+    hash: u64,
+    /// Zobrist key of the pawns alone, for the evaluation's pawn hash table
+    pawn_hash: u64,
+
+    /// Zobrist keys of the positions before each move played with `make_move`, oldest
+    /// first, for repetition detection
+    history: Vec<u64>,
 }
 
 impl Board {
@@ -39,19 +61,73 @@ impl Board {
             bb_pieces: [0.into(); PIECES_TYPE_COUNT],
             bb_player: [0.into(); PLAYERS_COUNT],
             mailbox: Mailbox::new(),
-            castling_rights: [[false; 2]; PLAYERS_COUNT],
+            castling_rights: [[None; 2]; PLAYERS_COUNT],
             en_passant: 0.into(),
             half_moves: 0,
             full_moves: 0,
-            previous: None
+            hash: 0,
+            pawn_hash: 0,
+            history: Vec::new(),
         }
     }
 
+    pub fn player(&self) -> Colour {
+        self.player
+    }
+
+    pub fn bb_pieces(&self, piece_type: PieceType) -> BitBoard {
+        self.bb_pieces[piece_type as usize]
+    }
+
+    pub fn bb_player(&self, colour: Colour) -> BitBoard {
+        self.bb_player[colour as usize]
+    }
+
+    /// Pieces of one type belonging to one side.
+    pub fn bb_pieces_of(&self, colour: Colour, piece_type: PieceType) -> BitBoard {
+        self.bb_player[colour as usize] & self.bb_pieces[piece_type as usize]
+    }
+
+    pub fn occupancy(&self) -> BitBoard {
+        self.bb_player[Colour::White as usize] | self.bb_player[Colour::Black as usize]
+    }
+
+    pub fn piece_on(&self, square: Square) -> Option<Piece> {
+        self.mailbox.get_piece(square)
+    }
+
+    pub fn en_passant(&self) -> Option<Square> {
+        if self.en_passant.is_empty() {
+            None
+        }
+        else {
+            Some(self.en_passant.try_into().unwrap())
+        }
+    }
+
+    /// Half moves since the last capture or pawn move, for the fifty-move rule.
+    pub fn half_moves(&self) -> u8 {
+        self.half_moves
+    }
+
+    /// The move number, starting at 1 and incremented after each black move.
+    pub fn full_moves(&self) -> u16 {
+        self.full_moves
+    }
+
+    pub fn castling_rights(&self, colour: Colour, side: CastlingRights) -> bool {
+        self.castling_rights[colour as usize][side as usize].is_some()
+    }
+
     pub fn set_piece(&mut self, square: Square, piece: Piece) {
         self.bb_player[piece.colour() as usize] |= square.into();
         self.bb_pieces[piece.piece_type() as usize] |= square.into();
 
         self.mailbox.set_piece(square, piece);
+        self.hash ^= ZOBRIST_KEYS.piece(square, piece);
+        if piece.piece_type() == PieceType::P {
+            self.pawn_hash ^= ZOBRIST_KEYS.piece(square, piece);
+        }
     }
 
     pub fn remove_square(&mut self, square: Square) {
@@ -62,13 +138,16 @@ impl Board {
             self.bb_player[p.colour() as usize] &= !s;
             self.bb_pieces[p.piece_type() as usize] &= !s;
             self.mailbox.remove_piece(square);
+            self.hash ^= ZOBRIST_KEYS.piece(square, p);
+            if p.piece_type() == PieceType::P {
+                self.pawn_hash ^= ZOBRIST_KEYS.piece(square, p);
+            }
         }
     }
 
     pub fn move_square(&mut self, from: Square, to: Square) {
-        self.remove_square(from);
-
         if let Some(fp) = self.mailbox.get_piece(from) {
+            self.remove_square(from);
             self.set_piece(to, fp);
         }
     }
@@ -0,0 +1,474 @@
+use crate::board::Board;
+use crate::board::castling;
+use crate::board::castling::CastlingRights;
+use crate::board::moves::{Move, MoveFlag, MoveList};
+use crate::board_representation::bitboard::BitBoard;
+use crate::board_representation::bitboard::files_ranks::{RANK_3_BITBOARD, RANK_6_BITBOARD, RANK_8_BITBOARD, RANK_1_BITBOARD};
+use crate::board_representation::bitboard::shift::Direction;
+use crate::board_representation::bitboard::shift::Direction::{North, NorthEast, NorthWest, South, SouthEast, SouthWest};
+use crate::board_representation::square::Square;
+use crate::piece::attacks::lines::{between, line};
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+
+const PROMOTIONS: [PieceType; 4] = [PieceType::Q, PieceType::R, PieceType::B, PieceType::N];
+
+impl Board {
+    /// Generates every pseudo-legal move for the side to move: moves that obey the
+    /// movement rules of each piece but may leave the king in check. Castling is only
+    /// generated when the king does not start in, pass through or land on an attacked square.
+    pub fn generate_pseudo_legal(&self, moves: &mut MoveList) {
+        let us = self.player;
+        let targets = !self.bb_player[us as usize];
+        let pins = Pins::none();
+
+        self.generate_pawn_moves(moves, targets, &pins);
+        for piece_type in [PieceType::N, PieceType::B, PieceType::R, PieceType::Q, PieceType::K].iter() {
+            self.generate_piece_moves(moves, *piece_type, targets, &pins);
+        }
+        self.generate_castling(moves);
+    }
+
+    /// Generates exactly the legal moves for the side to move.
+    ///
+    /// Rather than making each move and testing for check, the checkers, the pinned pieces
+    /// and a check-evasion target mask are computed up front. In double check only the king
+    /// may move; in single check other pieces must capture the checker or block between it
+    /// and the king; pinned pieces may only move along the line through their king.
+    pub fn generate_legal(&self, moves: &mut MoveList) {
+        self.generate_legal_targeting(moves, false);
+    }
+
+    /// Generates the legal captures (en passant included) and promotions for the side to
+    /// move, for the quiescence search. The opponent's pieces are the only targets, plus
+    /// the empty squares of the promotion rank for pawn pushes. Castling is never generated.
+    pub fn generate_captures(&self, moves: &mut MoveList) {
+        self.generate_legal_targeting(moves, true);
+    }
+
+    fn generate_legal_targeting(&self, moves: &mut MoveList, captures_only: bool) {
+        let us = self.player;
+        let them = us.opposite();
+        let king = match self.king_square(us) {
+            Some(k) => k,
+            None => return self.generate_pseudo_legal_targeting(moves, captures_only),
+        };
+        let occupancy = self.occupancy();
+        let own = self.bb_player[us as usize];
+        let enemies = self.bb_player[them as usize];
+        let king_targets = if captures_only { enemies } else { !own };
+
+        // The king may not step onto an attacked square, including squares "behind" itself
+        // along the line of a checking slider, so it is removed from the occupancy.
+        let without_king = occupancy & !BitBoard::from(king);
+        for to in (PieceType::king_attack(king) & king_targets).iter_squares() {
+            if self.attackers_by(to, them, without_king).is_empty() {
+                let flag = if (BitBoard::from(to) & self.bb_player[them as usize]).is_empty() {
+                    MoveFlag::Quiet
+                }
+                else {
+                    MoveFlag::Capture
+                };
+                moves.push(Move::new(king, to, flag));
+            }
+        }
+
+        let checkers = self.attackers_by(king, them, occupancy);
+        if checkers.count() > 1 {
+            return;
+        }
+
+        let evasions = match checkers.iter_squares().next() {
+            Some(checker) => BitBoard::from(checker) | between(king, checker),
+            None => !BitBoard::new(0),
+        };
+        let pins = Pins {
+            king: Some(king),
+            pinned: self.pinned(us),
+            checkers,
+        };
+
+        let (pawn_targets, targets) = self.targets(captures_only);
+        self.generate_pawn_moves(moves, pawn_targets & evasions, &pins);
+        for piece_type in [PieceType::N, PieceType::B, PieceType::R, PieceType::Q].iter() {
+            self.generate_piece_moves(moves, *piece_type, targets & evasions, &pins);
+        }
+        if checkers.is_empty() && !captures_only {
+            self.generate_castling(moves);
+        }
+    }
+
+    /// Pseudo-legal generation for boards without a king of the side to move.
+    fn generate_pseudo_legal_targeting(&self, moves: &mut MoveList, captures_only: bool) {
+        if !captures_only {
+            return self.generate_pseudo_legal(moves);
+        }
+        let (pawn_targets, targets) = self.targets(true);
+        let pins = Pins::none();
+
+        self.generate_pawn_moves(moves, pawn_targets, &pins);
+        for piece_type in [PieceType::N, PieceType::B, PieceType::R, PieceType::Q, PieceType::K].iter() {
+            self.generate_piece_moves(moves, *piece_type, targets, &pins);
+        }
+    }
+
+    /// The target squares for pawns and for the other pieces. Capture generation targets
+    /// the opponent's pieces, and lets pawns push onto the promotion rank as well.
+    fn targets(&self, captures_only: bool) -> (BitBoard, BitBoard) {
+        let us = self.player;
+        if !captures_only {
+            let targets = !self.bb_player[us as usize];
+            return (targets, targets);
+        }
+
+        let enemies = self.bb_player[us.opposite() as usize];
+        let promotion_rank = match us {
+            Colour::White => RANK_8_BITBOARD,
+            Colour::Black => RANK_1_BITBOARD,
+        };
+        (enemies | (promotion_rank & !self.occupancy()), enemies)
+    }
+
+    fn generate_pawn_moves(&self, moves: &mut MoveList, targets: BitBoard, pins: &Pins) {
+        let us = self.player;
+        let them = us.opposite();
+        let pawns = self.bb_pieces_of(us, PieceType::P);
+        let empty = !self.occupancy();
+        let enemies = self.bb_player[them as usize] & targets;
+
+        let (up, up_left, up_right, double_rank, promotion_rank) = match us {
+            Colour::White => (North, NorthWest, NorthEast, RANK_3_BITBOARD, RANK_8_BITBOARD),
+            Colour::Black => (South, SouthWest, SouthEast, RANK_6_BITBOARD, RANK_1_BITBOARD),
+        };
+        let down = opposite_direction(up);
+
+        // Pushes
+        let single = pawns.shift(up) & empty;
+        let double = (single & double_rank).shift(up) & empty & targets;
+        let single = single & targets;
+
+        for to in (single & !promotion_rank).iter_squares() {
+            pins.push(moves, Move::new(to.shift(down), to, MoveFlag::Quiet));
+        }
+        for to in (single & promotion_rank).iter_squares() {
+            if !(pins.allowed(to.shift(down)) & BitBoard::from(to)).is_empty() {
+                push_promotions(moves, to.shift(down), to, false);
+            }
+        }
+        for to in double.iter_squares() {
+            pins.push(moves, Move::new(to.shift(down).shift(down), to, MoveFlag::DoublePush));
+        }
+
+        // Captures
+        for direction in [up_left, up_right].iter() {
+            let back = opposite_direction(*direction);
+            let captures = pawns.shift(*direction) & enemies;
+
+            for to in (captures & !promotion_rank).iter_squares() {
+                pins.push(moves, Move::new(to.shift(back), to, MoveFlag::Capture));
+            }
+            for to in (captures & promotion_rank).iter_squares() {
+                if !(pins.allowed(to.shift(back)) & BitBoard::from(to)).is_empty() {
+                    push_promotions(moves, to.shift(back), to, true);
+                }
+            }
+        }
+
+        // En passant
+        if let Some(ep) = self.en_passant() {
+            let attackers = PieceType::pawn_attack(ep.into(), them) & pawns;
+            for from in attackers.iter_squares() {
+                if let Some(king) = pins.king {
+                    if !self.en_passant_legal(from, ep, down, king, pins.checkers) {
+                        continue;
+                    }
+                }
+                moves.push(Move::new(from, ep, MoveFlag::EnPassant));
+            }
+        }
+    }
+
+    /// En passant removes two pieces from the capturing rank at once, which can expose the
+    /// king to a rook or queen even though neither pawn is pinned on its own. It can also
+    /// resolve a check by removing the checking pawn, which the evasion mask doesn't see.
+    /// Both cases are settled by recomputing the king's attackers after the capture.
+    fn en_passant_legal(&self, from: Square, ep: Square, down: Direction, king: Square, checkers: BitBoard) -> bool {
+        let us = self.player;
+        let them = us.opposite();
+        let captured = BitBoard::from(ep.shift(down));
+        let occupancy = (self.occupancy() ^ BitBoard::from(from) ^ captured) | BitBoard::from(ep);
+
+        let queens = self.bb_pieces_of(them, PieceType::Q);
+        let diagonal = self.bb_pieces_of(them, PieceType::B) | queens;
+        let orthogonal = self.bb_pieces_of(them, PieceType::R) | queens;
+        let leapers = self.bb_pieces_of(them, PieceType::N) | self.bb_pieces_of(them, PieceType::P);
+
+        (checkers & leapers & !captured).is_empty()
+            && (PieceType::bishop_attack(king, occupancy) & diagonal).is_empty()
+            && (PieceType::rook_attack(king, occupancy) & orthogonal).is_empty()
+    }
+
+    fn generate_piece_moves(&self, moves: &mut MoveList, piece_type: PieceType, targets: BitBoard, pins: &Pins) {
+        let us = self.player;
+        let enemies = self.bb_player[us.opposite() as usize];
+        let occupancy = self.occupancy();
+
+        for from in self.bb_pieces_of(us, piece_type).iter_squares() {
+            let attacks = piece_attacks(piece_type, from, occupancy) & targets & pins.allowed(from);
+            for to in attacks.iter_squares() {
+                let flag = if (BitBoard::from(to) & enemies).is_empty() {
+                    MoveFlag::Quiet
+                }
+                else {
+                    MoveFlag::Capture
+                };
+                moves.push(Move::new(from, to, flag));
+            }
+        }
+    }
+
+    /// Castling in chess and Chess960 alike: every square the king and rook cross or land
+    /// on must be empty apart from the two of them, and the king may not start on, pass
+    /// through or land on an attacked square.
+    fn generate_castling(&self, moves: &mut MoveList) {
+        let us = self.player;
+        let them = us.opposite();
+        let occupancy = self.occupancy();
+        let king_from = match self.king_square(us) {
+            Some(king) => king,
+            None => return,
+        };
+
+        for side in [CastlingRights::KingSide, CastlingRights::QueenSide].iter() {
+            let rook_from = match self.castling_rook(us, *side) {
+                Some(rook) => rook,
+                None => continue,
+            };
+            let king_to = castling::square(side.king_to_file(), king_from.rank());
+            let rook_to = castling::square(side.rook_to_file(), king_from.rank());
+
+            let king_path = between(king_from, king_to) | king_to.into() | king_from.into();
+            let rook_path = between(rook_from, rook_to) | rook_to.into();
+            let castlers = BitBoard::from(king_from) | rook_from.into();
+            if !((king_path | rook_path) & occupancy & !castlers).is_empty() {
+                continue;
+            }
+            if king_path.iter_squares().any(|sq| self.attacked_by(sq, them, occupancy)) {
+                continue;
+            }
+            // A Chess960 rook can shield the king's destination from a slider along the
+            // back rank until it moves away
+            let after = occupancy ^ castlers | king_to.into() | rook_to.into();
+            if self.attacked_by(king_to, them, after) {
+                continue;
+            }
+
+            let flag = match side {
+                CastlingRights::KingSide => MoveFlag::KingCastle,
+                CastlingRights::QueenSide => MoveFlag::QueenCastle,
+            };
+            moves.push(Move::new(king_from, king_to, flag));
+        }
+    }
+}
+
+/// Pin and check information threaded through the generators. Pseudo-legal generation
+/// uses `Pins::none()`, which lets every move through.
+struct Pins {
+    king: Option<Square>,
+    pinned: BitBoard,
+    checkers: BitBoard,
+}
+
+impl Pins {
+    fn none() -> Self {
+        Self {
+            king: None,
+            pinned: 0.into(),
+            checkers: 0.into(),
+        }
+    }
+
+    /// Squares the piece on `from` may move to without exposing its king.
+    fn allowed(&self, from: Square) -> BitBoard {
+        match self.king {
+            Some(king) if !(self.pinned & BitBoard::from(from)).is_empty() => line(king, from),
+            _ => !BitBoard::new(0),
+        }
+    }
+
+    fn push(&self, moves: &mut MoveList, mv: Move) {
+        if !(self.allowed(mv.from()) & BitBoard::from(mv.to())).is_empty() {
+            moves.push(mv);
+        }
+    }
+}
+
+fn piece_attacks(piece_type: PieceType, square: Square, occupancy: BitBoard) -> BitBoard {
+    match piece_type {
+        PieceType::N => PieceType::knight_attack(square),
+        PieceType::B => PieceType::bishop_attack(square, occupancy),
+        PieceType::R => PieceType::rook_attack(square, occupancy),
+        PieceType::Q => PieceType::queen_attack(square, occupancy),
+        PieceType::K => PieceType::king_attack(square),
+        PieceType::P => unreachable!("pawn attacks depend on colour"),
+    }
+}
+
+fn push_promotions(moves: &mut MoveList, from: Square, to: Square, capture: bool) {
+    for piece_type in PROMOTIONS.iter() {
+        moves.push(Move::new_promotion(from, to, *piece_type, capture));
+    }
+}
+
+fn opposite_direction(direction: Direction) -> Direction {
+    match direction {
+        Direction::North => Direction::South,
+        Direction::NorthEast => Direction::SouthWest,
+        Direction::East => Direction::West,
+        Direction::SouthEast => Direction::NorthWest,
+        Direction::South => Direction::North,
+        Direction::SouthWest => Direction::NorthEast,
+        Direction::West => Direction::East,
+        Direction::NorthWest => Direction::SouthEast,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::moves::{MoveList, Move};
+
+    fn pseudo_legal(fen: &str) -> Vec<String> {
+        let board: Board = fen.parse().unwrap();
+        let mut moves = MoveList::new();
+        board.generate_pseudo_legal(&mut moves);
+        moves.iter().map(Move::to_string).collect()
+    }
+
+    fn legal(fen: &str) -> Vec<String> {
+        let board: Board = fen.parse().unwrap();
+        let mut moves = MoveList::new();
+        board.generate_legal(&mut moves);
+        moves.iter().map(Move::to_string).collect()
+    }
+
+    #[test]
+    fn movegen_starting() {
+        let moves = pseudo_legal("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(moves.len(), 20);
+        assert!(moves.contains(&"e2e4".to_string()));
+        assert!(moves.contains(&"g1f3".to_string()));
+    }
+
+    #[test]
+    fn movegen_kiwipete() {
+        let moves = pseudo_legal("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_eq!(moves.len(), 48);
+        assert!(moves.contains(&"e1g1".to_string()));
+        assert!(moves.contains(&"e1c1".to_string()));
+    }
+
+    #[test]
+    fn movegen_promotions_and_en_passant() {
+        let moves = pseudo_legal("r3k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        for promotion in ["b7b8q", "b7b8n", "b7a8r", "b7a8b", "e5d6", "e5e6"].iter() {
+            assert!(moves.contains(&promotion.to_string()), "missing {}", promotion);
+        }
+        // 8 promotions, e5e6, e5d6 and 5 king moves
+        assert_eq!(moves.len(), 15);
+    }
+
+    #[test]
+    fn movegen_castling_through_check() {
+        let moves = pseudo_legal("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1");
+        assert!(moves.contains(&"e8g8".to_string()));
+        assert!(moves.contains(&"e8c8".to_string()));
+
+        // The bishop on c4 covers f1, so only the queen side castle is available
+        let moves = pseudo_legal("4k3/8/8/8/2b5/8/8/R3K2R w KQ - 0 1");
+        assert!(!moves.contains(&"e1g1".to_string()));
+        assert!(moves.contains(&"e1c1".to_string()));
+    }
+
+    #[test]
+    fn legal_perft_positions() {
+        // Depth 1 counts of the standard chessprogramming.org perft positions
+        assert_eq!(legal("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").len(), 20);
+        assert_eq!(legal("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").len(), 48);
+        assert_eq!(legal("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").len(), 14);
+        assert_eq!(legal("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").len(), 6);
+        assert_eq!(legal("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").len(), 44);
+        assert_eq!(legal("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").len(), 46);
+    }
+
+    #[test]
+    fn legal_captures_match_legal_moves() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "4r2k/8/8/8/8/8/3B4/R3K3 w - - 0 1",
+            "r3k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        ];
+
+        for fen in fens.iter() {
+            let board: Board = fen.parse().unwrap();
+            let mut moves = MoveList::new();
+            board.generate_legal(&mut moves);
+            let mut expected: Vec<Move> = moves.iter().copied().filter(|m| m.is_capture() || m.is_promotion()).collect();
+
+            let mut captures = MoveList::new();
+            board.generate_captures(&mut captures);
+            let mut captures: Vec<Move> = captures.to_vec();
+
+            expected.sort_by_key(|m| u16::from(*m));
+            captures.sort_by_key(|m| u16::from(*m));
+            assert_eq!(captures, expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn legal_pinned_piece() {
+        // The knight on e2 is pinned by the rook on e8
+        let moves = legal("4r2k/8/8/8/8/8/4N3/4K3 w - - 0 1");
+        assert!(moves.iter().all(|m| !m.starts_with("e2")));
+
+        // The pinned rook may still slide along the pin and capture the pinner
+        let moves = legal("4r2k/8/8/8/8/8/4R3/4K3 w - - 0 1");
+        assert!(moves.contains(&"e2e8".to_string()));
+        assert!(!moves.contains(&"e2d2".to_string()));
+    }
+
+    #[test]
+    fn legal_check_evasions() {
+        // Single check from the rook on e8: block, capture or move the king
+        let moves = legal("4r2k/8/8/8/8/8/3B4/R3K3 w - - 0 1");
+        assert!(moves.contains(&"d2e3".to_string()));
+        assert!(!moves.contains(&"d2c3".to_string()));
+        assert!(!moves.contains(&"a1a2".to_string()));
+        assert!(!moves.contains(&"e1c1".to_string()));
+
+        // Double check: only king moves
+        let moves = legal("4r2k/8/8/8/1b6/8/3B4/R3K3 w Q - 0 1");
+        assert!(moves.iter().all(|m| m.starts_with("e1")));
+
+        // The king may not retreat along the checking ray
+        let moves = legal("4r2k/8/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(!moves.contains(&"e1e2".to_string()));
+        assert!(moves.contains(&"e1d1".to_string()));
+    }
+
+    #[test]
+    fn legal_en_passant_discovered_check() {
+        // Capturing en passant would remove both pawns from the fifth rank
+        let moves = legal("8/8/8/KPp4r/8/8/8/7k w - c6 0 2");
+        assert!(!moves.contains(&"b5c6".to_string()));
+        assert!(moves.contains(&"b5b6".to_string()));
+
+        // En passant capturing the checking pawn
+        let moves = legal("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1");
+        assert!(moves.contains(&"e4d3".to_string()));
+    }
+}
@@ -0,0 +1,395 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+use std::ops::{Deref, DerefMut};
+use crate::board_representation;
+use crate::board_representation::square::Square;
+use crate::piece::piecetype::PieceType;
+use crate::board::moves::ParseError::{Flag, Length, Promotion};
+
+const FROM_MASK: u16 = 0x003f;
+const TO_MASK: u16 = 0x0fc0;
+const TO_SHIFT: u16 = 6;
+const FLAG_SHIFT: u16 = 12;
+
+const PROMOTION_BIT: u16 = 0b1000;
+const CAPTURE_BIT: u16 = 0b0100;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ParseError {
+    #[error("invalid square: {0}")]
+    SquareParse(#[from] board_representation::square::ParseError),
+    #[error("invalid move length: {0}, expected 4 or 5")]
+    Length(usize),
+    #[error("invalid promotion piece: {0}")]
+    Promotion(char),
+    #[error("unused move flag: {0}")]
+    Flag(u16),
+}
+
+/// The special-move flag stored in the upper four bits of a `Move`.
+///
+/// Bit 3 marks a promotion and bit 2 a capture, so the promotion flags double up as
+/// capture flags and the promoted piece lives in the lower two bits.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum MoveFlag {
+    Quiet = 0,
+    DoublePush = 1,
+    KingCastle = 2,
+    QueenCastle = 3,
+    Capture = 4,
+    EnPassant = 5,
+    PromoteKnight = 8,
+    PromoteBishop = 9,
+    PromoteRook = 10,
+    PromoteQueen = 11,
+    CapturePromoteKnight = 12,
+    CapturePromoteBishop = 13,
+    CapturePromoteRook = 14,
+    CapturePromoteQueen = 15,
+}
+
+impl MoveFlag {
+    /// Only called on the flag bits of a `Move`, which `TryFrom<u16>` keeps to used values.
+    fn from_bits(bits: u16) -> Self {
+        match bits {
+            0 => MoveFlag::Quiet,
+            1 => MoveFlag::DoublePush,
+            2 => MoveFlag::KingCastle,
+            3 => MoveFlag::QueenCastle,
+            4 => MoveFlag::Capture,
+            5 => MoveFlag::EnPassant,
+            8 => MoveFlag::PromoteKnight,
+            9 => MoveFlag::PromoteBishop,
+            10 => MoveFlag::PromoteRook,
+            11 => MoveFlag::PromoteQueen,
+            12 => MoveFlag::CapturePromoteKnight,
+            13 => MoveFlag::CapturePromoteBishop,
+            14 => MoveFlag::CapturePromoteRook,
+            15 => MoveFlag::CapturePromoteQueen,
+            _ => unreachable!("unused move flag {}", bits),
+        }
+    }
+}
+
+/// A move packed into 16 bits: from-square (bits 0-5), to-square (bits 6-11) and a
+/// `MoveFlag` (bits 12-15).
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Move(u16);
+
+impl Move {
+    /// The null move, written as `0000` in long algebraic notation.
+    pub const NULL: Move = Move(0);
+
+    pub fn new(from: Square, to: Square, flag: MoveFlag) -> Self {
+        Self(
+            from.value() as u16
+                | (to.value() as u16) << TO_SHIFT
+                | (flag as u16) << FLAG_SHIFT
+        )
+    }
+
+    pub fn new_promotion(from: Square, to: Square, piece_type: PieceType, capture: bool) -> Self {
+        let flag = match (piece_type, capture) {
+            (PieceType::N, false) => MoveFlag::PromoteKnight,
+            (PieceType::B, false) => MoveFlag::PromoteBishop,
+            (PieceType::R, false) => MoveFlag::PromoteRook,
+            (PieceType::Q, false) => MoveFlag::PromoteQueen,
+            (PieceType::N, true) => MoveFlag::CapturePromoteKnight,
+            (PieceType::B, true) => MoveFlag::CapturePromoteBishop,
+            (PieceType::R, true) => MoveFlag::CapturePromoteRook,
+            (PieceType::Q, true) => MoveFlag::CapturePromoteQueen,
+            _ => panic!("cannot promote to {:?}", piece_type),
+        };
+        Self::new(from, to, flag)
+    }
+
+    pub fn from(self) -> Square {
+        u64::from(self.0 & FROM_MASK).try_into().unwrap()
+    }
+
+    pub fn to(self) -> Square {
+        u64::from((self.0 & TO_MASK) >> TO_SHIFT).try_into().unwrap()
+    }
+
+    pub fn flag(self) -> MoveFlag {
+        MoveFlag::from_bits(self.flag_bits())
+    }
+
+    pub fn promotion(self) -> Option<PieceType> {
+        if !self.is_promotion() {
+            return None;
+        }
+        Some(match self.flag_bits() & 0b11 {
+            0 => PieceType::N,
+            1 => PieceType::B,
+            2 => PieceType::R,
+            _ => PieceType::Q,
+        })
+    }
+
+    pub fn is_null(self) -> bool {
+        self == Move::NULL
+    }
+
+    pub fn is_capture(self) -> bool {
+        self.flag_bits() & CAPTURE_BIT != 0
+    }
+
+    pub fn is_promotion(self) -> bool {
+        self.flag_bits() & PROMOTION_BIT != 0
+    }
+
+    pub fn is_double_push(self) -> bool {
+        self.flag() == MoveFlag::DoublePush
+    }
+
+    pub fn is_en_passant(self) -> bool {
+        self.flag() == MoveFlag::EnPassant
+    }
+
+    pub fn is_castle(self) -> bool {
+        self.is_castle_kingside() || self.is_castle_queenside()
+    }
+
+    pub fn is_castle_kingside(self) -> bool {
+        self.flag() == MoveFlag::KingCastle
+    }
+
+    pub fn is_castle_queenside(self) -> bool {
+        self.flag() == MoveFlag::QueenCastle
+    }
+
+    /// Quiet moves are neither captures nor promotions.
+    pub fn is_quiet(self) -> bool {
+        self.flag_bits() & (PROMOTION_BIT | CAPTURE_BIT) == 0
+    }
+
+    fn flag_bits(self) -> u16 {
+        self.0 >> FLAG_SHIFT
+    }
+}
+
+impl From<Move> for u16 {
+    fn from(value: Move) -> Self {
+        value.0
+    }
+}
+
+/// Rebuilds a move from its packed form, e.g. as stored in the transposition table.
+/// Fails if the flag bits hold one of the two unused values, 6 or 7.
+impl TryFrom<u16> for Move {
+    type Error = ParseError;
+
+    fn try_from(value: u16) -> Result<Self, ParseError> {
+        match value >> FLAG_SHIFT {
+            6 | 7 => Err(Flag(value >> FLAG_SHIFT)),
+            _ => Ok(Self(value)),
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_null() {
+            return write!(f, "0000");
+        }
+        write!(f, "{}{}", self.from(), self.to())?;
+        match self.promotion() {
+            Some(PieceType::N) => write!(f, "n"),
+            Some(PieceType::B) => write!(f, "b"),
+            Some(PieceType::R) => write!(f, "r"),
+            Some(PieceType::Q) => write!(f, "q"),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?})", self, self.flag())
+    }
+}
+
+/// Parses a move in long algebraic notation (`e2e4`, `e7e8q`).
+///
+/// The string carries no information about captures, double pushes, en passant or
+/// castling, so the result only has its squares and promotion piece set. Match it
+/// against generated moves to recover the flags.
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let s = s.trim();
+        if s == "0000" {
+            return Ok(Move::NULL);
+        }
+
+        let chars: Vec<char> = s.chars().collect();
+        if chars.len() != 4 && chars.len() != 5 {
+            return Err(Length(chars.len()));
+        }
+
+        let from = Square::try_from((chars[0], chars[1]))?;
+        let to = Square::try_from((chars[2], chars[3]))?;
+
+        match chars.get(4) {
+            None => Ok(Move::new(from, to, MoveFlag::Quiet)),
+            Some(c) => {
+                let piece_type = match c {
+                    'n' | 'N' => PieceType::N,
+                    'b' | 'B' => PieceType::B,
+                    'r' | 'R' => PieceType::R,
+                    'q' | 'Q' => PieceType::Q,
+                    _ => return Err(Promotion(*c)),
+                };
+                Ok(Move::new_promotion(from, to, piece_type, false))
+            }
+        }
+    }
+}
+
+/// Upper bound on the number of moves in any reachable position (the known maximum is 218).
+pub const MAX_MOVES: usize = 256;
+
+/// A fixed-capacity, stack-allocated list of moves filled by the move generators.
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        Self {
+            moves: [Move::NULL; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, mv: Move) {
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &Self::Target {
+        &self.moves[..self.len]
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use crate::board::moves::{Move, MoveFlag, ParseError};
+    use crate::board_representation::square::Square;
+    use crate::piece::piecetype::PieceType;
+
+    #[test]
+    fn move_encoding() {
+        let from = Square::try_from(('e', '7')).unwrap();
+        let to = Square::try_from(('d', '8')).unwrap();
+        let mv = Move::new_promotion(from, to, PieceType::R, true);
+
+        assert_eq!(mv.from(), from);
+        assert_eq!(mv.to(), to);
+        assert_eq!(mv.flag(), MoveFlag::CapturePromoteRook);
+        assert_eq!(mv.promotion(), Some(PieceType::R));
+        assert!(mv.is_capture());
+        assert!(mv.is_promotion());
+        assert!(!mv.is_quiet());
+        assert_eq!(std::mem::size_of::<Move>(), 2);
+    }
+
+    #[test]
+    fn move_flags() {
+        let e1 = Square::try_from(('e', '1')).unwrap();
+        let g1 = Square::try_from(('g', '1')).unwrap();
+        let c1 = Square::try_from(('c', '1')).unwrap();
+
+        assert!(Move::new(e1, g1, MoveFlag::KingCastle).is_castle_kingside());
+        assert!(Move::new(e1, c1, MoveFlag::QueenCastle).is_castle_queenside());
+        assert!(Move::new(e1, g1, MoveFlag::KingCastle).is_quiet());
+        assert!(Move::new(e1, g1, MoveFlag::EnPassant).is_capture());
+        assert!(Move::new(e1, g1, MoveFlag::DoublePush).is_double_push());
+        assert_eq!(Move::new(e1, g1, MoveFlag::Capture).promotion(), None);
+    }
+
+    #[test]
+    fn move_display() {
+        let e2 = Square::try_from(('e', '2')).unwrap();
+        let e4 = Square::try_from(('e', '4')).unwrap();
+        assert_eq!(Move::new(e2, e4, MoveFlag::DoublePush).to_string(), "e2e4");
+
+        let e7 = Square::try_from(('e', '7')).unwrap();
+        let e8 = Square::try_from(('e', '8')).unwrap();
+        assert_eq!(Move::new_promotion(e7, e8, PieceType::Q, false).to_string(), "e7e8q");
+        assert_eq!(Move::NULL.to_string(), "0000");
+    }
+
+    #[test]
+    fn move_parse() {
+        let mv: Move = "a7b8n".parse().unwrap();
+        assert_eq!(mv.to_string(), "a7b8n");
+        assert_eq!(mv.promotion(), Some(PieceType::N));
+
+        let mv: Move = "g1f3".parse().unwrap();
+        assert_eq!(mv.flag(), MoveFlag::Quiet);
+        assert_eq!(mv.to_string(), "g1f3");
+
+        assert_eq!("0000".parse::<Move>().unwrap(), Move::NULL);
+    }
+
+    #[test]
+    fn move_from_packed() {
+        let e2 = Square::try_from(('e', '2')).unwrap();
+        let e4 = Square::try_from(('e', '4')).unwrap();
+        let mv = Move::new(e2, e4, MoveFlag::DoublePush);
+        assert_eq!(Move::try_from(u16::from(mv)).unwrap(), mv);
+
+        let unused = u16::from(Move::new(e2, e4, MoveFlag::Quiet));
+        assert!(matches!(Move::try_from(unused | 6 << 12), Err(ParseError::Flag(6))));
+        assert!(matches!(Move::try_from(unused | 7 << 12), Err(ParseError::Flag(7))));
+    }
+
+    #[test]
+    #[should_panic(expected = "Promotion")]
+    fn move_parse_invalid_promotion() {
+        let _mv: Move = "e7e8k".parse().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Length")]
+    fn move_parse_invalid_length() {
+        let _mv: Move = "e2e".parse().unwrap();
+    }
+}
@@ -0,0 +1,226 @@
+use std::fmt;
+use std::fmt::Formatter;
+use crate::board::Board;
+use crate::board::moves::MoveList;
+use crate::board_representation::bitboard::BitBoard;
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+
+/// Light squares, for telling apart bishops that can never meet.
+const LIGHT_SQUARES: BitBoard = BitBoard::new(0x55aa_55aa_55aa_55aa);
+
+/// The final score of a game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+/// Written as in PGN: `1-0`, `0-1` or `1/2-1/2`.
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GameResult::WhiteWins => write!(f, "1-0"),
+            GameResult::BlackWins => write!(f, "0-1"),
+            GameResult::Draw => write!(f, "1/2-1/2"),
+        }
+    }
+}
+
+/// Why a game is over.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Checkmate { winner: Colour },
+    Stalemate,
+    /// The same position occurred for the third time
+    Repetition,
+    /// Fifty moves by each side without a capture or a pawn move
+    FiftyMoves,
+    InsufficientMaterial,
+}
+
+impl Outcome {
+    pub fn result(self) -> GameResult {
+        match self {
+            Outcome::Checkmate { winner: Colour::White } => GameResult::WhiteWins,
+            Outcome::Checkmate { winner: Colour::Black } => GameResult::BlackWins,
+            _ => GameResult::Draw,
+        }
+    }
+}
+
+impl Board {
+    /// Whether the current position has occurred at least `count` times, counting this
+    /// occurrence, among the positions reached with `make_move`. Only positions since the
+    /// last capture or pawn move are scanned, since none before it can repeat.
+    pub fn is_repetition(&self, count: usize) -> bool {
+        let reversible = (self.half_moves as usize).min(self.history.len());
+        let occurrences = self.history.iter()
+            .rev()
+            .take(reversible)
+            // Only positions with the same side to move can match
+            .skip(1)
+            .step_by(2)
+            .filter(|key| **key == self.hash)
+            .count();
+        occurrences + 1 >= count
+    }
+
+    /// Whether fifty moves by each side have passed without a capture or a pawn move. A
+    /// checkmate delivered on the last of them still stands.
+    pub fn is_fifty_move_draw(&self) -> bool {
+        if self.half_moves < 100 {
+            return false;
+        }
+        if !self.in_check() {
+            return true;
+        }
+        let mut moves = MoveList::new();
+        self.generate_legal(&mut moves);
+        !moves.is_empty()
+    }
+
+    /// Whether neither side has the material to mate by any sequence of legal moves: bare
+    /// kings, a single minor piece, or only bishops that all stand on the same colour.
+    pub fn has_insufficient_material(&self) -> bool {
+        let heavy = self.bb_pieces(PieceType::P) | self.bb_pieces(PieceType::R) | self.bb_pieces(PieceType::Q);
+        if !heavy.is_empty() {
+            return false;
+        }
+
+        let knights = self.bb_pieces(PieceType::N);
+        let bishops = self.bb_pieces(PieceType::B);
+        if (knights | bishops).count() <= 1 {
+            return true;
+        }
+        knights.is_empty() && ((bishops & LIGHT_SQUARES).is_empty() || (bishops & !LIGHT_SQUARES).is_empty())
+    }
+
+    /// Whether the game is over, and why. Repetition means threefold repetition.
+    pub fn outcome(&self) -> Option<Outcome> {
+        let mut moves = MoveList::new();
+        self.generate_legal(&mut moves);
+        if moves.is_empty() {
+            return Some(if self.in_check() {
+                Outcome::Checkmate { winner: self.player.opposite() }
+            }
+            else {
+                Outcome::Stalemate
+            });
+        }
+
+        if self.is_repetition(3) {
+            Some(Outcome::Repetition)
+        }
+        else if self.half_moves >= 100 {
+            Some(Outcome::FiftyMoves)
+        }
+        else if self.has_insufficient_material() {
+            Some(Outcome::InsufficientMaterial)
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::fen::STARTING_FEN;
+    use crate::board::moves::MoveList;
+    use crate::board::outcome::{GameResult, Outcome};
+    use crate::piece::colour::Colour;
+    use crate::testing::play;
+
+    fn outcome(fen: &str) -> Option<Outcome> {
+        fen.parse::<Board>().unwrap().outcome()
+    }
+
+    #[test]
+    fn repetition() {
+        let mut board: Board = STARTING_FEN.parse().unwrap();
+        assert!(board.is_repetition(1));
+        assert!(!board.is_repetition(2));
+
+        play(&mut board, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert!(board.is_repetition(2));
+        assert!(!board.is_repetition(3));
+
+        play(&mut board, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert!(board.is_repetition(3));
+        assert_eq!(board.outcome(), Some(Outcome::Repetition));
+
+        // A pawn move makes every earlier position unreachable
+        play(&mut board, &["e2e4", "e7e5", "g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert!(board.is_repetition(2));
+        assert!(!board.is_repetition(3));
+    }
+
+    #[test]
+    fn repetition_undone() {
+        let mut board: Board = STARTING_FEN.parse().unwrap();
+        play(&mut board, &["g1f3", "g8f6", "f3g1"]);
+
+        let mut list = MoveList::new();
+        board.generate_legal(&mut list);
+        let mv = *list.iter().find(|m| m.to_string() == "f6g8").unwrap();
+        let undo = board.make_move(mv);
+        assert!(board.is_repetition(2));
+        board.unmake_move(mv, undo);
+        assert!(!board.is_repetition(2));
+    }
+
+    #[test]
+    fn fifty_moves() {
+        assert!(!"4k3/8/8/8/8/8/8/R3K3 w - - 99 80".parse::<Board>().unwrap().is_fifty_move_draw());
+        assert!("4k3/8/8/8/8/8/8/R3K3 w - - 100 80".parse::<Board>().unwrap().is_fifty_move_draw());
+        assert_eq!(outcome("4k3/8/8/8/8/8/8/R3K3 w - - 100 80"), Some(Outcome::FiftyMoves));
+        // Mate on the hundredth half move is still mate
+        let mated: Board = "R3k3/8/4K3/8/8/8/8/8 b - - 100 80".parse().unwrap();
+        assert!(!mated.is_fifty_move_draw());
+        assert_eq!(mated.outcome(), Some(Outcome::Checkmate { winner: Colour::White }));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let insufficient = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/4K3 w - - 0 1",
+            // Bishops on light squares only
+            "4k3/8/8/8/8/8/8/3BKB2 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/4K1B1 w - - 0 1",
+        ];
+        for fen in insufficient.iter() {
+            assert!(fen.parse::<Board>().unwrap().has_insufficient_material(), "{}", fen);
+        }
+
+        let sufficient = [
+            STARTING_FEN,
+            "4k3/8/8/8/8/8/8/4KNN1 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1",
+            "4kn2/8/8/8/8/8/8/4KB2 w - - 0 1",
+            "4k3/7p/8/8/8/8/8/4K3 w - - 0 1",
+        ];
+        for fen in sufficient.iter() {
+            assert!(!fen.parse::<Board>().unwrap().has_insufficient_material(), "{}", fen);
+        }
+        assert_eq!(outcome("4k3/8/8/8/8/8/8/4KN2 w - - 0 1"), Some(Outcome::InsufficientMaterial));
+    }
+
+    #[test]
+    fn outcomes() {
+        assert_eq!(outcome(STARTING_FEN), None);
+        // Fool's mate
+        let mate = outcome("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        assert_eq!(mate, Outcome::Checkmate { winner: Colour::Black });
+        assert_eq!(mate.result(), GameResult::BlackWins);
+        assert_eq!(mate.result().to_string(), "0-1");
+
+        let stalemate = outcome("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(stalemate, Outcome::Stalemate);
+        assert_eq!(stalemate.result().to_string(), "1/2-1/2");
+    }
+}
@@ -0,0 +1,302 @@
+use std::convert::TryFrom;
+use crate::board::Board;
+use crate::board::moves::{Move, MoveFlag, MoveList};
+use crate::board_representation;
+use crate::board_representation::square::Square;
+use crate::piece::piecetype::PieceType;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SanError {
+    #[error("empty move")]
+    Empty,
+    #[error("invalid square: {0}")]
+    SquareParse(#[from] board_representation::square::ParseError),
+    #[error("malformed move: {0}")]
+    Syntax(String),
+    #[error("illegal move: {0}")]
+    Illegal(String),
+    #[error("ambiguous move: {0}")]
+    Ambiguous(String),
+}
+
+fn piece_letter(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::P => 'P',
+        PieceType::N => 'N',
+        PieceType::B => 'B',
+        PieceType::R => 'R',
+        PieceType::Q => 'Q',
+        PieceType::K => 'K',
+    }
+}
+
+/// The piece named by an upper case SAN letter. Pawns have no letter in SAN.
+fn piece_from_letter(c: char) -> Option<PieceType> {
+    match c {
+        'N' => Some(PieceType::N),
+        'B' => Some(PieceType::B),
+        'R' => Some(PieceType::R),
+        'Q' => Some(PieceType::Q),
+        'K' => Some(PieceType::K),
+        _ => None,
+    }
+}
+
+impl Board {
+    /// Writes a legal move in Standard Algebraic Notation, e.g. `Nbd7`, `exd6`, `e8=Q+` or
+    /// `O-O-O#`. The origin is only given when another piece of the same type could also
+    /// reach the destination, preferring the file, then the rank, then both.
+    pub fn to_san(&self, mv: Move) -> String {
+        let mut san = match mv.flag() {
+            MoveFlag::KingCastle => String::from("O-O"),
+            MoveFlag::QueenCastle => String::from("O-O-O"),
+            _ => self.to_san_without_suffix(mv),
+        };
+
+        let mut after = self.clone();
+        after.make_move(mv);
+        if after.in_check() {
+            let mut replies = MoveList::new();
+            after.generate_legal(&mut replies);
+            san.push(if replies.is_empty() { '#' } else { '+' });
+        }
+        san
+    }
+
+    fn to_san_without_suffix(&self, mv: Move) -> String {
+        let (from, to) = (mv.from(), mv.to());
+        let piece_type = self.piece_on(from).expect("no piece to move").piece_type();
+        let mut san = String::new();
+
+        if piece_type == PieceType::P {
+            if mv.is_capture() {
+                san.push((b'a' + from.file() as u8) as char);
+                san.push('x');
+            }
+            san.push_str(&to.to_string());
+            if let Some(promotion) = mv.promotion() {
+                san.push('=');
+                san.push(piece_letter(promotion));
+            }
+            return san;
+        }
+
+        san.push(piece_letter(piece_type));
+
+        let mut moves = MoveList::new();
+        self.generate_legal(&mut moves);
+        let rivals: Vec<Square> = moves.iter()
+            .filter(|m| m.to() == to && m.from() != from)
+            .map(|m| m.from())
+            .filter(|sq| self.piece_on(*sq).map(|p| p.piece_type()) == Some(piece_type))
+            .collect();
+        if !rivals.is_empty() {
+            let file = (b'a' + from.file() as u8) as char;
+            let rank = (b'1' + from.rank() as u8) as char;
+            if rivals.iter().all(|sq| sq.file() != from.file()) {
+                san.push(file);
+            }
+            else if rivals.iter().all(|sq| sq.rank() != from.rank()) {
+                san.push(rank);
+            }
+            else {
+                san.push(file);
+                san.push(rank);
+            }
+        }
+
+        if mv.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&to.to_string());
+        san
+    }
+
+    /// Reads a move in Standard Algebraic Notation and finds it among the legal moves.
+    ///
+    /// Castling may be written with zeros (`0-0`), a promotion with or without `=`, and
+    /// check, mate and annotation suffixes (`+`, `#`, `!`, `?`) are ignored. A capture
+    /// sign is optional, but the origin must be given whenever the move would otherwise
+    /// be ambiguous.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        if text.is_empty() {
+            return Err(SanError::Empty);
+        }
+
+        let mut moves = MoveList::new();
+        self.generate_legal(&mut moves);
+
+        let castle = match text {
+            "O-O" | "0-0" => Some(MoveFlag::KingCastle),
+            "O-O-O" | "0-0-0" => Some(MoveFlag::QueenCastle),
+            _ => None,
+        };
+        if let Some(flag) = castle {
+            return moves.iter()
+                .copied()
+                .find(|m| m.flag() == flag)
+                .ok_or_else(|| SanError::Illegal(san.to_string()));
+        }
+
+        let mut chars: Vec<char> = text.chars().collect();
+
+        let piece_type = match chars.first().copied().and_then(piece_from_letter) {
+            Some(piece_type) => {
+                chars.remove(0);
+                piece_type
+            }
+            None => PieceType::P,
+        };
+
+        let mut promotion = None;
+        if piece_type == PieceType::P {
+            if let Some(piece) = chars.last().copied().and_then(piece_from_letter) {
+                if piece == PieceType::K {
+                    return Err(SanError::Syntax(san.to_string()));
+                }
+                promotion = Some(piece);
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+            }
+        }
+
+        if chars.len() < 2 {
+            return Err(SanError::Syntax(san.to_string()));
+        }
+        let rank = chars.pop().unwrap();
+        let file = chars.pop().unwrap();
+        let to = Square::try_from((file, rank))?;
+
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' if from_file.is_none() && from_rank.is_none() => from_file = Some(c as u64 - 'a' as u64),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(c as u64 - '1' as u64),
+                _ => return Err(SanError::Syntax(san.to_string())),
+            }
+        }
+
+        let mut candidates = moves.iter().copied().filter(|m| {
+            m.to() == to
+                && !m.is_castle()
+                && m.promotion() == promotion
+                && self.piece_on(m.from()).map(|p| p.piece_type()) == Some(piece_type)
+                && from_file.is_none_or(|f| m.from().file() == f)
+                && from_rank.is_none_or(|r| m.from().rank() == r)
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(mv), None) => Ok(mv),
+            (None, _) => Err(SanError::Illegal(san.to_string())),
+            (Some(_), Some(_)) => Err(SanError::Ambiguous(san.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::fen::STARTING_FEN;
+    use crate::board::moves::MoveList;
+    use crate::board::san::SanError;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn san(fen: &str, uci: &str) -> String {
+        let board: Board = fen.parse().unwrap();
+        let mut moves = MoveList::new();
+        board.generate_legal(&mut moves);
+        let mv = *moves.iter().find(|m| m.to_string() == uci).unwrap();
+        board.to_san(mv)
+    }
+
+    fn parse(fen: &str, san: &str) -> Result<String, SanError> {
+        let board: Board = fen.parse().unwrap();
+        board.parse_san(san).map(|m| m.to_string())
+    }
+
+    #[test]
+    fn to_san() {
+        assert_eq!(san(STARTING_FEN, "e2e4"), "e4");
+        assert_eq!(san(STARTING_FEN, "g1f3"), "Nf3");
+        assert_eq!(san(KIWIPETE, "e1g1"), "O-O");
+        assert_eq!(san(KIWIPETE, "e1c1"), "O-O-O");
+        assert_eq!(san(KIWIPETE, "d5e6"), "dxe6");
+        assert_eq!(san(KIWIPETE, "e2a6"), "Bxa6");
+        assert_eq!(san(KIWIPETE, "f3f6"), "Qxf6");
+        assert_eq!(san(KIWIPETE, "e5f7"), "Nxf7");
+        assert_eq!(san(KIWIPETE, "c3b5"), "Nb5");
+        assert_eq!(san("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), "b8=Q+");
+        assert_eq!(san("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8n"), "bxa8=N");
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+    }
+
+    #[test]
+    fn to_san_disambiguation() {
+        // Knights on b1 and f3 both reach d2
+        assert_eq!(san("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1", "b1d2"), "Nbd2");
+        // Rooks on a1 and a5 share the file
+        assert_eq!(san("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1a3"), "R1a3");
+        // Queens on a1, c1 and a3 all reach b2: a1 shares the rank with c1 and the file
+        // with a3
+        assert_eq!(san("8/7k/8/8/8/Q7/8/Q1Q4K w - - 0 1", "a1b2"), "Qa1b2");
+        assert_eq!(san("8/7k/8/8/8/Q7/8/Q1Q4K w - - 0 1", "a3b2"), "Q3b2");
+        assert_eq!(san("8/7k/8/8/8/Q7/8/Q1Q4K w - - 0 1", "c1b2"), "Qcb2");
+    }
+
+    #[test]
+    fn parse_san() {
+        assert_eq!(parse(STARTING_FEN, "e4").unwrap(), "e2e4");
+        assert_eq!(parse(STARTING_FEN, "Nf3").unwrap(), "g1f3");
+        assert_eq!(parse(KIWIPETE, "O-O").unwrap(), "e1g1");
+        assert_eq!(parse(KIWIPETE, "0-0-0").unwrap(), "e1c1");
+        assert_eq!(parse(KIWIPETE, "dxe6").unwrap(), "d5e6");
+        assert_eq!(parse(KIWIPETE, "Qxf6!?").unwrap(), "f3f6");
+        assert_eq!(parse(KIWIPETE, "Qf6").unwrap(), "f3f6");
+        assert_eq!(parse("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8=Q+").unwrap(), "b7b8q");
+        assert_eq!(parse("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "bxa8N").unwrap(), "b7a8n");
+        assert_eq!(parse("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1", "Nbd2").unwrap(), "b1d2");
+        assert_eq!(parse("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "R1a3").unwrap(), "a1a3");
+        assert_eq!(parse("8/7k/8/8/8/Q7/8/Q1Q4K w - - 0 1", "Qa1b2").unwrap(), "a1b2");
+    }
+
+    #[test]
+    fn parse_san_errors() {
+        assert!(matches!(parse(STARTING_FEN, ""), Err(SanError::Empty)));
+        assert!(matches!(parse(STARTING_FEN, "e5"), Err(SanError::Illegal(_))));
+        assert!(matches!(parse(STARTING_FEN, "O-O"), Err(SanError::Illegal(_))));
+        assert!(matches!(parse(STARTING_FEN, "Nz3"), Err(SanError::SquareParse(_))));
+        assert!(matches!(parse(STARTING_FEN, "N"), Err(SanError::Syntax(_))));
+        assert!(matches!(parse(STARTING_FEN, "Nqf3"), Err(SanError::Syntax(_))));
+        assert!(matches!(parse("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1", "Nd2"), Err(SanError::Ambiguous(_))));
+        // Promotion is not optional
+        assert!(matches!(parse("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8"), Err(SanError::Illegal(_))));
+    }
+
+    #[test]
+    fn san_round_trip() {
+        let positions = [
+            STARTING_FEN,
+            KIWIPETE,
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/7k/8/8/8/Q7/8/Q1Q4K w - - 0 1",
+        ];
+        for fen in positions.iter() {
+            let board: Board = fen.parse().unwrap();
+            let mut moves = MoveList::new();
+            board.generate_legal(&mut moves);
+            for mv in moves.iter() {
+                let san = board.to_san(*mv);
+                assert_eq!(board.parse_san(&san).unwrap(), *mv, "{} in {}", san, fen);
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::board::Board;
+use crate::board::moves::Move;
+use crate::board_representation::bitboard::BitBoard;
+use crate::board_representation::bitboard::shift::Direction;
+use crate::board_representation::square::Square;
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+
+/// Rough piece values, used by the exchange evaluation and by the search for move ordering
+/// and pruning. The king can never be captured, so its value never enters the result.
+pub const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+/// Attackers are tried from least to most valuable.
+const ATTACKER_ORDER: [PieceType; 6] = [PieceType::P, PieceType::N, PieceType::B, PieceType::R, PieceType::Q, PieceType::K];
+
+impl Board {
+    /// Static exchange evaluation: the material the side to move wins (or, if negative,
+    /// loses) by playing `mv` and letting both sides recapture on the target square with
+    /// their least valuable attacker for as long as it pays. Sliders hidden behind a
+    /// capturing piece join in as it leaves the square. Pins are not taken into account,
+    /// and the king only recaptures when the opponent has nothing left to recapture with.
+    pub fn see(&self, mv: Move) -> i32 {
+        if mv.is_castle() {
+            return 0;
+        }
+
+        let from = mv.from();
+        let to = mv.to();
+        let mut occupancy = self.occupancy() ^ BitBoard::from(from);
+
+        let mut gain = [0; 32];
+        gain[0] = if mv.is_en_passant() {
+            let captured = match self.player {
+                Colour::White => to.shift(Direction::South),
+                Colour::Black => to.shift(Direction::North),
+            };
+            occupancy ^= BitBoard::from(captured);
+            PIECE_VALUES[PieceType::P as usize]
+        }
+        else {
+            self.piece_on(to).map_or(0, |p| PIECE_VALUES[p.piece_type() as usize])
+        };
+
+        // The value of the piece standing on the square, which the next capture wins
+        let mut on_square = match mv.promotion() {
+            Some(promoted) => {
+                gain[0] += PIECE_VALUES[promoted as usize] - PIECE_VALUES[PieceType::P as usize];
+                PIECE_VALUES[promoted as usize]
+            }
+            None => self.piece_on(from).map_or(0, |p| PIECE_VALUES[p.piece_type() as usize]),
+        };
+
+        let mut side = self.player.opposite();
+        let mut attackers = self.attackers_to(to, occupancy) & occupancy;
+        let mut depth = 0;
+
+        loop {
+            let ours = attackers & self.bb_player(side);
+            let (piece_type, square) = match self.least_valuable(ours) {
+                Some(attacker) => attacker,
+                None => break,
+            };
+            if piece_type == PieceType::K && !(attackers & self.bb_player(side.opposite())).is_empty() {
+                break;
+            }
+
+            depth += 1;
+            gain[depth] = on_square - gain[depth - 1];
+            on_square = PIECE_VALUES[piece_type as usize];
+
+            // Removing the attacker may uncover a slider behind it
+            occupancy ^= BitBoard::from(square);
+            let queens = self.bb_pieces(PieceType::Q);
+            if matches!(piece_type, PieceType::P | PieceType::B | PieceType::Q) {
+                attackers |= PieceType::bishop_attack(to, occupancy) & (self.bb_pieces(PieceType::B) | queens);
+            }
+            if matches!(piece_type, PieceType::R | PieceType::Q) {
+                attackers |= PieceType::rook_attack(to, occupancy) & (self.bb_pieces(PieceType::R) | queens);
+            }
+            attackers &= occupancy;
+            side = side.opposite();
+
+            if depth == gain.len() - 1 {
+                break;
+            }
+        }
+
+        // Either side may stop capturing when continuing would lose material
+        while depth > 0 {
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+            depth -= 1;
+        }
+        gain[0]
+    }
+
+    /// Whether the static exchange evaluation of `mv` is at least `threshold`. Cheaper than
+    /// `see`, as it stops as soon as one side can no longer change the answer.
+    pub fn see_ge(&self, mv: Move, threshold: i32) -> bool {
+        if mv.is_castle() {
+            return threshold <= 0;
+        }
+
+        let from = mv.from();
+        let to = mv.to();
+        let mut occupancy = self.occupancy() ^ BitBoard::from(from);
+
+        // `swap` is what the side that just captured stands to gain over the threshold,
+        // if the piece it left on the square is not taken
+        let mut swap = if mv.is_en_passant() {
+            let captured = match self.player {
+                Colour::White => to.shift(Direction::South),
+                Colour::Black => to.shift(Direction::North),
+            };
+            occupancy ^= BitBoard::from(captured);
+            PIECE_VALUES[PieceType::P as usize]
+        }
+        else {
+            self.piece_on(to).map_or(0, |p| PIECE_VALUES[p.piece_type() as usize])
+        };
+        let on_square = match mv.promotion() {
+            Some(promoted) => {
+                swap += PIECE_VALUES[promoted as usize] - PIECE_VALUES[PieceType::P as usize];
+                PIECE_VALUES[promoted as usize]
+            }
+            None => self.piece_on(from).map_or(0, |p| PIECE_VALUES[p.piece_type() as usize]),
+        };
+
+        swap -= threshold;
+        if swap < 0 {
+            return false;
+        }
+        // Even losing the capturing piece for nothing keeps the threshold
+        swap = on_square - swap;
+        if swap <= 0 {
+            return true;
+        }
+
+        let mut side = self.player;
+        let mut attackers = self.attackers_to(to, occupancy) & occupancy;
+        // Whether the side to move gets its threshold if the capturing stops here
+        let mut result = true;
+
+        loop {
+            side = side.opposite();
+            let ours = attackers & self.bb_player(side);
+            let (piece_type, square) = match self.least_valuable(ours) {
+                Some(attacker) => attacker,
+                None => break,
+            };
+            if piece_type == PieceType::K && !(attackers & self.bb_player(side.opposite())).is_empty() {
+                break;
+            }
+
+            result = !result;
+            swap = PIECE_VALUES[piece_type as usize] - swap;
+            if swap < result as i32 {
+                break;
+            }
+
+            // Removing the attacker may uncover a slider behind it
+            occupancy ^= BitBoard::from(square);
+            let queens = self.bb_pieces(PieceType::Q);
+            if matches!(piece_type, PieceType::P | PieceType::B | PieceType::Q) {
+                attackers |= PieceType::bishop_attack(to, occupancy) & (self.bb_pieces(PieceType::B) | queens);
+            }
+            if matches!(piece_type, PieceType::R | PieceType::Q) {
+                attackers |= PieceType::rook_attack(to, occupancy) & (self.bb_pieces(PieceType::R) | queens);
+            }
+            attackers &= occupancy;
+        }
+        result
+    }
+
+    fn least_valuable(&self, attackers: BitBoard) -> Option<(PieceType, Square)> {
+        ATTACKER_ORDER.iter().find_map(|piece_type| {
+            (attackers & self.bb_pieces(*piece_type)).iter_squares().next().map(|square| (*piece_type, square))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::moves::MoveList;
+    use crate::testing::find_move;
+
+    fn see(fen: &str, name: &str) -> i32 {
+        let board: Board = fen.parse().unwrap();
+        let mv = find_move(&board, name);
+
+        assert!(board.see_ge(mv, board.see(mv)));
+        assert!(!board.see_ge(mv, board.see(mv) + 1));
+        board.see(mv)
+    }
+
+    #[test]
+    fn see_undefended_and_defended() {
+        // Undefended pawn
+        assert_eq!(see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5"), 100);
+        // Pawn defended by a pawn: the rook is lost for it
+        assert_eq!(see("4k3/8/5p2/4p3/8/8/8/K3R3 w - - 0 1", "e1e5"), 100 - 500);
+        // Quiet move onto an attacked square
+        assert_eq!(see("4k3/8/5p2/8/8/8/8/K3N3 w - - 0 1", "e1d3"), 0);
+        assert_eq!(see("4k3/8/5p2/8/2N5/8/8/K7 w - - 0 1", "c4e5"), -320);
+    }
+
+    #[test]
+    fn see_x_rays() {
+        // Nxe5 Nxe5 Rxe5 Bxe5 Qxe5 Rxe5: the knight is lost for a pawn
+        assert_eq!(see("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3e5"), 100 - 320);
+        // The queen behind the rook makes the exchange on d5 win a pawn
+        assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/3QK3 w - - 0 1", "d2d5"), 100);
+        // Without the queen the rook is lost
+        assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"), 100 - 500);
+    }
+
+    #[test]
+    fn see_king_recaptures_only_when_safe() {
+        assert_eq!(see("8/8/3k4/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"), 100 - 500);
+        // With the queen behind the rook the king can't recapture
+        assert_eq!(see("8/8/3k4/3p4/8/8/3R4/3QK3 w - - 0 1", "d2d5"), 100);
+    }
+
+    #[test]
+    fn see_promotion_and_en_passant() {
+        assert_eq!(see("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q"), 500 + 800);
+        assert_eq!(see("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q"), 800 - 900);
+        assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 100);
+        assert_eq!(see("4k3/1n6/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 0);
+    }
+
+    #[test]
+    fn see_ge_agrees_with_see() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+            "r1bq1rk1/pp2bppp/2n1pn2/2pp4/2PP4/2NBPN2/PP3PPP/R1BQ1RK1 b - - 0 1",
+            "1r2k3/P4n2/8/3pP3/2Q5/8/8/4K3 w - d6 0 1",
+        ];
+        for fen in fens.iter() {
+            let board: Board = fen.parse().unwrap();
+            let mut moves = MoveList::new();
+            board.generate_legal(&mut moves);
+            for mv in moves.iter() {
+                let see = board.see(*mv);
+                for threshold in (-1000..=1400).step_by(10) {
+                    assert_eq!(board.see_ge(*mv, threshold), see >= threshold, "{} {} {}", fen, mv, threshold);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use once_cell::sync::Lazy;
+use crate::board::{Board, PIECES_TYPE_COUNT, PLAYERS_COUNT, SQUARES, FILES};
+use crate::board_representation::square::Square;
+use crate::common::prng::Prng;
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+use crate::piece::Piece;
+
+const ZOBRIST_SEED: u64 = 0x646f_6766_6973_6821;
+
+pub struct ZobristKeys {
+    pub pieces: [[[u64; SQUARES]; PIECES_TYPE_COUNT]; PLAYERS_COUNT],
+    /// XORed in when black is to move
+    pub side: u64,
+    pub castling: [[u64; 2]; PLAYERS_COUNT],
+    pub en_passant: [u64; FILES],
+    /// XORed in with `castling` for the file of the rook, which differs between
+    /// Chess960 positions
+    pub castling_file: [[u64; FILES]; PLAYERS_COUNT],
+}
+
+pub static ZOBRIST_KEYS: Lazy<ZobristKeys> = Lazy::new(|| {
+    let mut prng = Prng::new(ZOBRIST_SEED);
+    let mut keys = ZobristKeys {
+        pieces: [[[0; SQUARES]; PIECES_TYPE_COUNT]; PLAYERS_COUNT],
+        side: 0,
+        castling: [[0; 2]; PLAYERS_COUNT],
+        en_passant: [0; FILES],
+        castling_file: [[0; FILES]; PLAYERS_COUNT],
+    };
+
+    for colour in keys.pieces.iter_mut() {
+        for piece_type in colour.iter_mut() {
+            for key in piece_type.iter_mut() {
+                *key = prng.next_u64();
+            }
+        }
+    }
+    keys.side = prng.next_u64();
+    for key in keys.castling.iter_mut().flatten() {
+        *key = prng.next_u64();
+    }
+    for key in keys.en_passant.iter_mut() {
+        *key = prng.next_u64();
+    }
+    for key in keys.castling_file.iter_mut().flatten() {
+        *key = prng.next_u64();
+    }
+    keys
+});
+
+impl ZobristKeys {
+    pub fn piece(&self, square: Square, piece: Piece) -> u64 {
+        self.pieces[piece.colour() as usize][piece.piece_type() as usize][square.value() as usize]
+    }
+
+    pub fn castling_rights(&self, rights: &[[Option<u8>; 2]; PLAYERS_COUNT]) -> u64 {
+        let mut hash = 0;
+        for (colour, sides) in rights.iter().enumerate() {
+            for (side, rook) in sides.iter().enumerate() {
+                if let Some(file) = rook {
+                    hash ^= self.castling[colour][side] ^ self.castling_file[colour][*file as usize];
+                }
+            }
+        }
+        hash
+    }
+}
+
+impl Board {
+    /// The Zobrist key of the position, maintained incrementally.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Computes the Zobrist key by visiting every piece. `make_move` and `unmake_move`
+    /// check the incremental key against this in debug builds.
+    pub fn compute_hash_from_scratch(&self) -> u64 {
+        let mut hash = 0;
+        for colour in [Colour::White, Colour::Black].iter() {
+            for piece_type in [PieceType::P, PieceType::N, PieceType::B, PieceType::R, PieceType::Q, PieceType::K].iter() {
+                for square in self.bb_pieces_of(*colour, *piece_type).iter_squares() {
+                    hash ^= ZOBRIST_KEYS.piece(square, (*colour, *piece_type).into());
+                }
+            }
+        }
+        if self.player == Colour::Black {
+            hash ^= ZOBRIST_KEYS.side;
+        }
+        hash ^= ZOBRIST_KEYS.castling_rights(&self.castling_rights);
+        hash ^ self.en_passant_key()
+    }
+
+    /// The Zobrist key of the pawns alone, maintained incrementally. Positions with the
+    /// same pawns share it whatever the other pieces, side to move or castling rights.
+    pub fn pawn_hash(&self) -> u64 {
+        self.pawn_hash
+    }
+
+    pub fn compute_pawn_hash_from_scratch(&self) -> u64 {
+        let mut hash = 0;
+        for colour in [Colour::White, Colour::Black].iter() {
+            for square in self.bb_pieces_of(*colour, PieceType::P).iter_squares() {
+                hash ^= ZOBRIST_KEYS.piece(square, (*colour, PieceType::P).into());
+            }
+        }
+        hash
+    }
+
+    /// The en passant file only enters the key when a pawn of the side to move could
+    /// actually capture, so that positions differing only in an unusable en passant square
+    /// hash (and repeat) alike.
+    pub(crate) fn en_passant_key(&self) -> u64 {
+        match self.capturable_en_passant() {
+            Some(ep) => ZOBRIST_KEYS.en_passant[ep.file() as usize],
+            None => 0,
+        }
+    }
+
+    /// The en passant square, if a pawn of the side to move stands next to the pawn that
+    /// just pushed. The capture is not checked for legality.
+    pub(crate) fn capturable_en_passant(&self) -> Option<Square> {
+        self.en_passant().filter(|ep| {
+            let capturers = PieceType::pawn_attack((*ep).into(), self.player.opposite())
+                & self.bb_pieces_of(self.player, PieceType::P);
+            !capturers.is_empty()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::fen::STARTING_FEN;
+    use crate::testing::play;
+
+    #[test]
+    fn zobrist_pawn_hash() {
+        let mut board: Board = STARTING_FEN.parse().unwrap();
+        let start = board.pawn_hash();
+        play(&mut board, &["g1f3", "g8f6"]);
+        assert_eq!(board.pawn_hash(), start);
+
+        play(&mut board, &["e2e4", "d7d5", "e4d5", "f6d5"]);
+        assert_ne!(board.pawn_hash(), start);
+        assert_eq!(board.pawn_hash(), board.compute_pawn_hash_from_scratch());
+
+        let parsed: Board = board.to_string().parse().unwrap();
+        assert_eq!(board.pawn_hash(), parsed.pawn_hash());
+    }
+
+    #[test]
+    fn zobrist_transposition() {
+        let mut a: Board = STARTING_FEN.parse().unwrap();
+        let mut b: Board = STARTING_FEN.parse().unwrap();
+        play(&mut a, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+        play(&mut b, &["b1c3", "b8c6", "g1f3", "g8f6"]);
+
+        assert_eq!(a.hash(), b.hash());
+        assert_eq!(a.hash(), a.compute_hash_from_scratch());
+    }
+
+    #[test]
+    fn zobrist_round_trip() {
+        let mut board: Board = STARTING_FEN.parse().unwrap();
+        let start = board.hash();
+        play(&mut board, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert_eq!(board.hash(), start);
+    }
+
+    #[test]
+    fn zobrist_matches_fen() {
+        let mut board: Board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        play(&mut board, &["e1g1", "a8b8", "a2a4"]);
+
+        let fen = board.to_string();
+        let parsed: Board = fen.parse().unwrap();
+        assert_eq!(board.hash(), parsed.hash());
+
+        // a2a4 can be taken en passant by the pawn on b4, so the en passant square counts
+        let without_ep: Board = fen.replace(" a3 ", " - ").parse().unwrap();
+        assert_ne!(board.hash(), without_ep.hash());
+    }
+
+    #[test]
+    fn zobrist_side_and_castling() {
+        let white: Board = "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1".parse().unwrap();
+        let black: Board = "4k3/8/8/8/8/8/8/R3K3 b Q - 0 1".parse().unwrap();
+        let no_castle: Board = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1".parse().unwrap();
+
+        assert_ne!(white.hash(), black.hash());
+        assert_ne!(white.hash(), no_castle.hash());
+    }
+
+    #[test]
+    fn zobrist_castling_rook_file() {
+        // Both rooks stand on the king side, only the one that may castle differs
+        let d_rook: Board = "k7/8/8/8/8/8/8/1K1R3R w D - 0 1".parse().unwrap();
+        let h_rook: Board = "k7/8/8/8/8/8/8/1K1R3R w H - 0 1".parse().unwrap();
+        assert_ne!(d_rook.hash(), h_rook.hash());
+        assert_eq!(d_rook.hash(), d_rook.compute_hash_from_scratch());
+    }
+
+    #[test]
+    fn zobrist_unusable_en_passant() {
+        // No black pawn can capture on e3, so the en passant square is ignored
+        let with_ep: Board = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".parse().unwrap();
+        let without_ep: Board = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".parse().unwrap();
+        assert_eq!(with_ep.hash(), without_ep.hash());
+    }
+}
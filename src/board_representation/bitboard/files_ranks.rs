@@ -16,4 +16,20 @@ pub const RANK_4_BITBOARD: BitBoard = BitBoard { board: 0xff00_0000};
 pub const RANK_5_BITBOARD: BitBoard = BitBoard { board: 0x00ff_0000_0000};
 pub const RANK_6_BITBOARD: BitBoard = BitBoard { board: 0xff00_0000_0000};
 pub const RANK_7_BITBOARD: BitBoard = BitBoard { board: 0x00ff_0000_0000_0000};
-pub const RANK_8_BITBOARD: BitBoard = BitBoard { board: 0xff00_0000_0000_0000};
\ No newline at end of file
+pub const RANK_8_BITBOARD: BitBoard = BitBoard { board: 0xff00_0000_0000_0000};
+pub const FILE_BITBOARDS: [BitBoard; 8] = [
+    FILE_A_BITBOARD, FILE_B_BITBOARD, FILE_C_BITBOARD, FILE_D_BITBOARD,
+    FILE_E_BITBOARD, FILE_F_BITBOARD, FILE_G_BITBOARD, FILE_H_BITBOARD,
+];
+
+/// The files on either side of each file.
+pub const ADJACENT_FILE_BITBOARDS: [BitBoard; 8] = [
+    FILE_B_BITBOARD,
+    BitBoard { board: FILE_A_BITBOARD.board | FILE_C_BITBOARD.board },
+    BitBoard { board: FILE_B_BITBOARD.board | FILE_D_BITBOARD.board },
+    BitBoard { board: FILE_C_BITBOARD.board | FILE_E_BITBOARD.board },
+    BitBoard { board: FILE_D_BITBOARD.board | FILE_F_BITBOARD.board },
+    BitBoard { board: FILE_E_BITBOARD.board | FILE_G_BITBOARD.board },
+    BitBoard { board: FILE_F_BITBOARD.board | FILE_H_BITBOARD.board },
+    FILE_G_BITBOARD,
+];
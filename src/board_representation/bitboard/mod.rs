@@ -1,5 +1,6 @@
 use std::fmt;
 use std::fmt::Formatter;
+use std::convert::TryFrom;
 use itertools::Itertools;
 use crate::board_representation::square::Square;
 use crate::board_representation::bitboard::files_ranks::{RANK_1_BITBOARD, FILE_A_BITBOARD};
@@ -8,7 +9,7 @@ pub mod files_ranks;
 pub mod shift;
 pub mod ops;
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Default, Eq, PartialEq)]
 pub struct BitBoard {
     board: u64,
 }
@@ -24,14 +25,41 @@ impl BitBoard {
         (0..64).rev().map(move |x| (self.board >> x) & 1 == 1)
     }
 
-    pub fn bitboard_of_rank(square: Square) -> Self {
-        let r = square.value() >> 3;
-        RANK_1_BITBOARD << BitBoard::from(8 * r)
+    /// Iterates over the occupied squares, from a1 towards h8.
+    pub fn iter_squares(self) -> impl Iterator<Item = Square> {
+        let mut board = self.board;
+        std::iter::from_fn(move || {
+            if board == 0 {
+                return None;
+            }
+            let square = Square::try_from(board.trailing_zeros() as u64).unwrap();
+            board &= board - 1;
+            Some(square)
+        })
     }
 
-    pub fn bitboard_of_file(square: Square) -> Self {
-        let f = square.value() & 7;
-        FILE_A_BITBOARD << BitBoard::from(f)
+    pub const fn is_empty(self) -> bool {
+        self.board == 0
+    }
+
+    pub const fn count(self) -> u32 {
+        self.board.count_ones()
+    }
+
+    pub const fn value(self) -> u64 {
+        self.board
+    }
+
+    pub const fn from_square(square: Square) -> Self {
+        Self::new(1 << square.value())
+    }
+
+    pub const fn bitboard_of_rank(square: Square) -> Self {
+        Self::new(RANK_1_BITBOARD.board << (8 * square.rank()))
+    }
+
+    pub const fn bitboard_of_file(square: Square) -> Self {
+        Self::new(FILE_A_BITBOARD.board << square.file())
     }
 }
 
@@ -45,9 +73,7 @@ impl From<u64> for BitBoard {
 
 impl From<Square> for BitBoard {
     fn from(value: Square) -> Self {
-        Self {
-            board: 1u64 << value.value()
-        }
+        Self::from_square(value)
     }
 }
 
@@ -59,7 +85,7 @@ impl From<BitBoard> for u64 {
 
 impl fmt::Display for BitBoard {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", format!("{:#018x}", self.board))
+        write!(f, "{:#018x}", self.board)
     }
 }
 
@@ -12,19 +12,42 @@ pub enum Direction {
     West,
     NorthWest,
 }
-impl BitBoard {
-    pub fn shift(self, direction: Direction) -> Self {
-        match direction {
-            Direction::North => { self << 8.into() },
-            Direction::NorthEast => { (self & !FILE_H_BITBOARD) << 9.into() },
-            Direction::East => { (self & !FILE_H_BITBOARD) << 1.into() },
-            Direction::SouthEast => { (self & !FILE_H_BITBOARD) >> 7.into() },
-            Direction::South => {self >> 8.into()},
-            Direction::SouthWest => { (self & !FILE_A_BITBOARD) >> 9.into() },
-            Direction::West => { (self & !FILE_A_BITBOARD) >> 1.into() },
-            Direction::NorthWest => { (self & !FILE_A_BITBOARD) << 7.into() },
+impl Direction {
+    /// The change in rank and file of one step in this direction.
+    pub const fn offsets(self) -> (i8, i8) {
+        match self {
+            Direction::North => (1, 0),
+            Direction::NorthEast => (1, 1),
+            Direction::East => (0, 1),
+            Direction::SouthEast => (-1, 1),
+            Direction::South => (-1, 0),
+            Direction::SouthWest => (-1, -1),
+            Direction::West => (0, -1),
+            Direction::NorthWest => (1, -1),
         }
     }
+
+    pub const fn steps(directions: [Direction; 4]) -> [(i8, i8); 4] {
+        [directions[0].offsets(), directions[1].offsets(), directions[2].offsets(), directions[3].offsets()]
+    }
+}
+
+impl BitBoard {
+    pub const fn shift(self, direction: Direction) -> Self {
+        let board = self.board;
+        let not_a = !FILE_A_BITBOARD.board;
+        let not_h = !FILE_H_BITBOARD.board;
+        Self::new(match direction {
+            Direction::North => { board << 8 },
+            Direction::NorthEast => { (board & not_h) << 9 },
+            Direction::East => { (board & not_h) << 1 },
+            Direction::SouthEast => { (board & not_h) >> 7 },
+            Direction::South => { board >> 8 },
+            Direction::SouthWest => { (board & not_a) >> 9 },
+            Direction::West => { (board & not_a) >> 1 },
+            Direction::NorthWest => { (board & not_a) << 7 },
+        })
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,6 @@
 use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::fmt::Formatter;
 use crate::board_representation::square::ParseError::{InvalidSquare, InvalidRankFile, BitBoardNotUnit};
 use crate::board_representation::bitboard::BitBoard;
 use crate::board_representation::bitboard::shift::Direction;
@@ -13,7 +15,7 @@ pub enum ParseError {
     BitBoardNotUnit
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct Square(u64);
 
 impl TryFrom<u64> for Square {
@@ -109,7 +111,20 @@ impl From<Square> for u64 {
     }
 }
 
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file() as u8) as char, self.rank() + 1)
+    }
+}
+
 impl Square {
+    /// Panics if `value` is not in 0..64; for use where the square is known to be valid,
+    /// including constant evaluation. Use `TryFrom` otherwise.
+    pub const fn new(value: u64) -> Self {
+        assert!(value < 64, "square must be 0..64");
+        Square(value)
+    }
+
     pub fn valid_square(sq: u64) -> bool {
         (0..64).contains(&sq)
     }
@@ -118,6 +133,21 @@ impl Square {
         self.0
     }
 
+    pub const fn file(self) -> u64 {
+        self.0 & 7
+    }
+
+    pub const fn rank(self) -> u64 {
+        self.0 >> 3
+    }
+
+    /// The number of king moves between two squares on an empty board.
+    pub fn distance(self, other: Square) -> u64 {
+        let files = (self.file() as i64 - other.file() as i64).abs();
+        let ranks = (self.rank() as i64 - other.rank() as i64).abs();
+        files.max(ranks) as u64
+    }
+
     pub fn shift(self, direction: Direction) -> Self {
         BitBoard::from(self).shift(direction).try_into().unwrap()
     }
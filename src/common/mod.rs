@@ -1 +1 @@
-
+pub mod prng;
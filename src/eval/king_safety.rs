@@ -0,0 +1,113 @@
+use crate::board::Board;
+use crate::board_representation::bitboard::BitBoard;
+use crate::board_representation::bitboard::files_ranks::FILE_BITBOARDS;
+use crate::board_representation::bitboard::shift::Direction;
+use crate::board_representation::square::Square;
+use crate::eval::Tapered;
+use crate::eval::params::EvalParams;
+use crate::eval::pawns::relative_rank;
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+
+/// How much of the summed king attack weights counts, in percent, by the number of
+/// attacking pieces: a lone attacker is rarely dangerous.
+const ATTACKER_SCALE: [i32; 8] = [0, 0, 50, 75, 88, 94, 97, 99];
+
+/// The squares around the king and one more rank towards the enemy.
+pub fn king_zone(king: Square, colour: Colour) -> BitBoard {
+    let near = PieceType::king_attack(king) | BitBoard::from(king);
+    let up = match colour {
+        Colour::White => Direction::North,
+        Colour::Black => Direction::South,
+    };
+    near | near.shift(up)
+}
+
+/// The bonus for attacking the enemy king, given the attacks gathered with the mobility.
+pub fn king_attack(attack: Tapered, attackers: u32) -> Tapered {
+    attack * ATTACKER_SCALE[(attackers as usize).min(ATTACKER_SCALE.len() - 1)] / 100
+}
+
+/// Pawn shelter and storm on the three files around `us`'s king. The files are taken
+/// around the b- or g-file for a king on the edge, so that the same three files count.
+pub fn shelter_and_storm(board: &Board, params: &EvalParams, us: Colour) -> Tapered {
+    let king = match board.bb_pieces_of(us, PieceType::K).iter_squares().next() {
+        Some(king) => king,
+        None => return Tapered::default(),
+    };
+    let ours = board.bb_pieces_of(us, PieceType::P);
+    let theirs = board.bb_pieces_of(us.opposite(), PieceType::P);
+    // Only pawns level with or in front of the king shelter or storm it
+    let in_front = in_front_of(king, us);
+
+    let centre = king.file().clamp(1, 6) as usize;
+    let mut score = Tapered::default();
+    for file in FILE_BITBOARDS[centre - 1..=centre + 1].iter() {
+        let shelter = nearest(ours & *file & in_front, us).map_or(0, |sq| relative_rank(sq, us));
+        let storm = nearest(theirs & *file & in_front, us).map_or(0, |sq| relative_rank(sq, us));
+        score += params.pawn_shelter[shelter] + params.pawn_storm[storm];
+    }
+    score
+}
+
+/// The ranks from the king's rank towards the enemy.
+fn in_front_of(king: Square, colour: Colour) -> BitBoard {
+    let rank = king.rank();
+    let bits = match colour {
+        Colour::White => u64::MAX << (8 * rank),
+        Colour::Black => u64::MAX >> (8 * (7 - rank)),
+    };
+    bits.into()
+}
+
+/// The square of `pawns` closest to `colour`'s back rank.
+fn nearest(pawns: BitBoard, colour: Colour) -> Option<Square> {
+    match colour {
+        Colour::White => pawns.iter_squares().next(),
+        Colour::Black => pawns.iter_squares().last(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use crate::board::Board;
+    use crate::board_representation::square::Square;
+    use crate::eval::king_safety::{king_zone, shelter_and_storm};
+    use crate::eval::params::EvalParams;
+    use crate::piece::colour::Colour;
+
+    fn shelter(fen: &str, colour: Colour) -> i32 {
+        let board: Board = fen.parse().unwrap();
+        shelter_and_storm(&board, &EvalParams::default(), colour).mg
+    }
+
+    #[test]
+    fn king_zone_squares() {
+        let g1 = Square::try_from(('g', '1')).unwrap();
+        // f1-h1, f2-h2 and f3-h3
+        assert_eq!(king_zone(g1, Colour::White).count(), 9);
+        let a8 = Square::try_from(('a', '8')).unwrap();
+        // a8-b8, a7-b7, a6-b6
+        assert_eq!(king_zone(a8, Colour::Black).count(), 6);
+    }
+
+    #[test]
+    fn shelter_intact_beats_broken() {
+        let intact = shelter("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1", Colour::White);
+        let advanced = shelter("6k1/5ppp/8/8/8/6P1/5P1P/6K1 w - - 0 1", Colour::White);
+        let open = shelter("6k1/5ppp/8/8/8/8/5P1P/6K1 w - - 0 1", Colour::White);
+        assert!(intact > advanced);
+        assert!(advanced > open);
+
+        // Symmetric positions shelter both kings alike
+        assert_eq!(intact, shelter("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1", Colour::Black));
+    }
+
+    #[test]
+    fn storm_pawns_close_in() {
+        let far = shelter("6k1/8/8/6p1/8/8/5PPP/6K1 w - - 0 1", Colour::White);
+        let near = shelter("6k1/8/8/8/8/6p1/5PPP/6K1 w - - 0 1", Colour::White);
+        assert!(near < far);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::board::Board;
+use crate::board_representation::bitboard::BitBoard;
+use crate::eval::Tapered;
+use crate::eval::king_safety::king_zone;
+use crate::eval::params::EvalParams;
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+
+/// What `us`'s pieces achieve with their attacks.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Activity {
+    pub mobility: Tapered,
+    /// Sum of the `king_attack` weights of every attack into the enemy king zone
+    pub king_attack: Tapered,
+    /// Number of pieces attacking the enemy king zone
+    pub king_attackers: u32,
+}
+
+/// Mobility of `us`'s knights, bishops, rooks and queens, and their attacks on the enemy
+/// king zone. Squares held by `us`'s own pieces or covered by enemy pawns don't count
+/// towards mobility, since a piece can't usefully go there.
+pub fn evaluate_activity(board: &Board, params: &EvalParams, us: Colour) -> Activity {
+    let them = us.opposite();
+    let occupancy = board.occupancy();
+    let enemy_pawn_attacks = PieceType::pawn_attack(board.bb_pieces_of(them, PieceType::P), them);
+    let area = !board.bb_player(us) & !enemy_pawn_attacks;
+    let zone = match board.bb_pieces_of(them, PieceType::K).iter_squares().next() {
+        Some(king) => king_zone(king, them),
+        None => BitBoard::new(0),
+    };
+
+    let mut activity = Activity::default();
+    for piece_type in [PieceType::N, PieceType::B, PieceType::R, PieceType::Q].iter() {
+        let table: &[Tapered] = match piece_type {
+            PieceType::N => &params.mobility_knight,
+            PieceType::B => &params.mobility_bishop,
+            PieceType::R => &params.mobility_rook,
+            _ => &params.mobility_queen,
+        };
+
+        for square in board.bb_pieces_of(us, *piece_type).iter_squares() {
+            let attacks = match piece_type {
+                PieceType::N => PieceType::knight_attack(square),
+                PieceType::B => PieceType::bishop_attack(square, occupancy),
+                PieceType::R => PieceType::rook_attack(square, occupancy),
+                _ => PieceType::queen_attack(square, occupancy),
+            };
+            activity.mobility += table[(attacks & area).count() as usize];
+
+            let zone_attacks = (attacks & zone).count() as i32;
+            if zone_attacks > 0 {
+                activity.king_attack += params.king_attack[*piece_type as usize] * zone_attacks;
+                activity.king_attackers += 1;
+            }
+        }
+    }
+    activity
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::eval::mobility::evaluate_activity;
+    use crate::eval::params::EvalParams;
+    use crate::piece::colour::Colour;
+
+    fn activity(fen: &str, colour: Colour) -> (i32, u32) {
+        let board: Board = fen.parse().unwrap();
+        let activity = evaluate_activity(&board, &EvalParams::default(), colour);
+        (activity.mobility.mg, activity.king_attackers)
+    }
+
+    #[test]
+    fn mobility_counts_safe_squares() {
+        // A centralised knight has all eight squares
+        let params = EvalParams::default();
+        assert_eq!(activity("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1", Colour::White).0, params.mobility_knight[8].mg);
+        // b5 and f5 are covered by the pawns on c6 and e6, which it may still capture
+        assert_eq!(activity("4k3/8/2p1p3/8/3N4/8/8/4K3 w - - 0 1", Colour::White).0, params.mobility_knight[6].mg);
+        // Own pieces block the bishop
+        assert!(activity("4k3/8/8/8/8/8/1P6/B3K3 w - - 0 1", Colour::White).0
+            < activity("4k3/8/8/8/8/8/8/B3K3 w - - 0 1", Colour::White).0);
+    }
+
+    #[test]
+    fn mobility_king_attackers() {
+        assert_eq!(activity("6k1/8/8/8/8/8/8/4K3 w - - 0 1", Colour::White).1, 0);
+        // The queen on g4 and the knight on h5 both reach into the zone around g8
+        assert_eq!(activity("6k1/5ppp/8/7N/6Q1/8/8/4K3 w - - 0 1", Colour::White).1, 2);
+    }
+}
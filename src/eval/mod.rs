@@ -0,0 +1,245 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+use crate::board::Board;
+use crate::eval::king_safety::{king_attack, shelter_and_storm};
+use crate::eval::mobility::evaluate_activity;
+use crate::eval::params::EvalParams;
+use crate::eval::pawns::{evaluate_passed, PawnTable};
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+
+pub mod king_safety;
+pub mod mobility;
+pub mod params;
+pub mod pawns;
+
+/// Game phase weight of each piece type; the starting position has the full `MAX_PHASE`.
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+pub const MAX_PHASE: i32 = 24;
+
+/// A pair of midgame and endgame values, blended by game phase at the end of evaluation.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Tapered {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Tapered {
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Self { mg, eg }
+    }
+
+    /// Interpolates between the endgame value at phase 0 and the midgame value at
+    /// `MAX_PHASE`.
+    pub fn blend(self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Tapered {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+
+impl AddAssign for Tapered {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Tapered {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl SubAssign for Tapered {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Tapered {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.mg, -self.eg)
+    }
+}
+
+impl Mul<i32> for Tapered {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self {
+        Self::new(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+impl Div<i32> for Tapered {
+    type Output = Self;
+
+    fn div(self, rhs: i32) -> Self {
+        Self::new(self.mg / rhs, self.eg / rhs)
+    }
+}
+
+/// Written as `mg/eg`.
+impl fmt::Display for Tapered {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.mg, self.eg)
+    }
+}
+
+/// Static evaluation of positions. Holds a pawn hash table, so evaluating takes `&mut self`.
+pub struct Evaluator {
+    params: EvalParams,
+    pawn_table: PawnTable,
+}
+
+impl Evaluator {
+    pub fn new(params: EvalParams) -> Self {
+        Self {
+            params,
+            pawn_table: PawnTable::new(),
+        }
+    }
+
+    pub fn params(&self) -> &EvalParams {
+        &self.params
+    }
+
+    /// The score of `board` in centipawns, from the side to move's point of view.
+    pub fn evaluate(&mut self, board: &Board) -> i32 {
+        let pawns = self.pawn_table.probe(board, &self.params);
+        let score = self.material_and_psqt(board)
+            + pawns.score
+            + evaluate_passed(board, &self.params, pawns.passed[Colour::White as usize], Colour::White)
+            - evaluate_passed(board, &self.params, pawns.passed[Colour::Black as usize], Colour::Black)
+            + self.activity_and_king_safety(board, Colour::White)
+            - self.activity_and_king_safety(board, Colour::Black);
+
+        let score = score.blend(phase(board));
+        match board.player() {
+            Colour::White => score,
+            Colour::Black => -score,
+        }
+    }
+
+    /// `us`'s mobility, attacks on the enemy king and own king shelter, from `us`'s point
+    /// of view.
+    fn activity_and_king_safety(&self, board: &Board, us: Colour) -> Tapered {
+        let activity = evaluate_activity(board, &self.params, us);
+        activity.mobility
+            + king_attack(activity.king_attack, activity.king_attackers)
+            + shelter_and_storm(board, &self.params, us)
+    }
+
+    /// Piece values plus piece-square table values, from White's point of view.
+    fn material_and_psqt(&self, board: &Board) -> Tapered {
+        let mut score = Tapered::default();
+        for (idx, piece_type) in PIECE_TYPES.iter().enumerate() {
+            let value = self.params.piece_values[idx];
+            let table = &self.params.psqt[idx];
+
+            // The tables are laid out rank 8 first, so White's squares are flipped
+            for square in board.bb_pieces_of(Colour::White, *piece_type).iter_squares() {
+                score += value + table[square.value() as usize ^ 56];
+            }
+            for square in board.bb_pieces_of(Colour::Black, *piece_type).iter_squares() {
+                score -= value + table[square.value() as usize];
+            }
+        }
+        score
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new(EvalParams::default())
+    }
+}
+
+const PIECE_TYPES: [PieceType; 6] = [PieceType::P, PieceType::N, PieceType::B, PieceType::R, PieceType::Q, PieceType::K];
+
+/// The game phase from the non-pawn material left on the board: `MAX_PHASE` with all
+/// pieces on the board, down to 0 with only kings and pawns. Promotions can push the
+/// count over the maximum, so it is capped.
+pub fn phase(board: &Board) -> i32 {
+    let phase: i32 = PIECE_TYPES.iter()
+        .zip(PHASE_WEIGHTS.iter())
+        .map(|(piece_type, weight)| board.bb_pieces(*piece_type).count() as i32 * weight)
+        .sum();
+    phase.min(MAX_PHASE)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::fen::STARTING_FEN;
+    use crate::eval::{phase, Evaluator, Tapered, MAX_PHASE};
+
+    fn evaluate(fen: &str) -> i32 {
+        Evaluator::default().evaluate(&fen.parse().unwrap())
+    }
+
+    #[test]
+    fn tapered_blend() {
+        let score = Tapered::new(100, 20);
+        assert_eq!(score.blend(MAX_PHASE), 100);
+        assert_eq!(score.blend(0), 20);
+        assert_eq!(score.blend(MAX_PHASE / 2), 60);
+        assert_eq!(score.to_string(), "100/20");
+    }
+
+    #[test]
+    fn eval_phase() {
+        assert_eq!(phase(&STARTING_FEN.parse().unwrap()), MAX_PHASE);
+        assert_eq!(phase(&"4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1".parse().unwrap()), 0);
+        assert_eq!(phase(&"3qk3/8/8/8/8/8/8/2R1K3 w - - 0 1".parse().unwrap()), 6);
+    }
+
+    #[test]
+    fn eval_symmetric() {
+        // The starting position, and any position mirrored with colours swapped, balance out
+        assert_eq!(evaluate(STARTING_FEN), 0);
+        let board: Board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        let mirrored: Board = "r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1".parse().unwrap();
+        let mut evaluator = Evaluator::default();
+        assert_eq!(evaluator.evaluate(&board), evaluator.evaluate(&mirrored));
+    }
+
+    #[test]
+    fn eval_side_to_move() {
+        let white = evaluate("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let black = evaluate("4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+        assert!(white > 800);
+        assert_eq!(white, -black);
+    }
+
+    #[test]
+    fn eval_passed_pawn_race() {
+        // The same passed pawn is worth more with the defending king far away
+        let near = evaluate("8/8/8/2k5/P7/8/8/4K3 w - - 0 1");
+        let far = evaluate("7k/8/8/8/P7/8/8/4K3 w - - 0 1");
+        assert!(far > near);
+    }
+
+    #[test]
+    fn eval_king_safety() {
+        // Same material; Black's king has lost its shelter and White's pieces eye it
+        let safe = evaluate("r5k1/5ppp/8/8/8/8/5PPP/R2Q2K1 w - - 0 1");
+        let exposed = evaluate("r5k1/8/5ppp/8/6Q1/8/5PPP/R5K1 w - - 0 1");
+        assert!(exposed > safe);
+    }
+
+    #[test]
+    fn eval_prefers_central_knights() {
+        assert!(evaluate("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1") > evaluate("4k3/8/8/8/8/8/8/N3K3 w - - 0 1"));
+    }
+}
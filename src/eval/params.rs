@@ -0,0 +1,427 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::fs;
+use std::path::Path;
+use std::slice;
+use std::str::FromStr;
+use crate::board::PIECES_TYPE_COUNT;
+use crate::eval::Tapered;
+
+/// Piece letters used in parameter names, in `PieceType` order.
+const PIECE_NAMES: [char; PIECES_TYPE_COUNT] = ['P', 'N', 'B', 'R', 'Q', 'K'];
+
+#[derive(thiserror::Error, Debug)]
+pub enum ParamsError {
+    #[error("could not read parameters: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("unknown parameter: {0}")]
+    UnknownKey(String),
+    #[error("value without a parameter name: {0}")]
+    MissingKey(String),
+    #[error("invalid value for {key}: {value}, expected mg/eg")]
+    Value { key: String, value: String },
+    #[error("{key} takes {expected} values, found {found}")]
+    Count { key: String, expected: usize, found: usize },
+}
+
+/// Every tunable number of the evaluation, each a midgame/endgame pair.
+///
+/// Parameters can be loaded from text so that tuned values don't need a rebuild. The format
+/// is a parameter name followed by its values written `mg/eg`, separated by any whitespace;
+/// `#` starts a comment. Names missing from the text keep their default values.
+///
+/// ```text
+/// piece_values 82/94 337/281 365/297 477/512 1025/936 0/0
+/// psqt.N -167/-58 -89/-38 ...
+/// doubled_pawn -10/-25
+/// ```
+///
+/// Piece-square tables hold 64 values laid out as a board seen from White's side, rank 8
+/// first; Black uses the same tables mirrored vertically. Tables by rank are indexed by the
+/// rank relative to the pawn's owner, its second rank being 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalParams {
+    pub piece_values: [Tapered; PIECES_TYPE_COUNT],
+    pub psqt: [[Tapered; 64]; PIECES_TYPE_COUNT],
+
+    pub doubled_pawn: Tapered,
+    pub isolated_pawn: Tapered,
+    pub backward_pawn: Tapered,
+    pub connected_pawn: [Tapered; 8],
+    pub candidate_pawn: [Tapered; 8],
+    pub passed_pawn: [Tapered; 8],
+    /// Bonus for a passed pawn with no piece on the squares up to promotion
+    pub passed_free_path: [Tapered; 8],
+    /// Per square of distance from the king to the passed pawn's stop square, scaled up
+    /// the further the pawn has advanced
+    pub passed_own_king_distance: Tapered,
+    pub passed_enemy_king_distance: Tapered,
+
+    /// Indexed by the number of squares a piece attacks, leaving out squares held by its own
+    /// pieces or attacked by enemy pawns
+    pub mobility_knight: [Tapered; 9],
+    pub mobility_bishop: [Tapered; 14],
+    pub mobility_rook: [Tapered; 15],
+    pub mobility_queen: [Tapered; 28],
+    /// Per square of the enemy king zone attacked, by attacking piece type
+    pub king_attack: [Tapered; PIECES_TYPE_COUNT],
+    /// By the relative rank of the nearest own pawn in front of the king on each of the
+    /// three files around it, 0 for none
+    pub pawn_shelter: [Tapered; 8],
+    /// By the relative rank of the nearest enemy pawn in front of the king on each of the
+    /// three files around it, 0 for none
+    pub pawn_storm: [Tapered; 8],
+}
+
+impl EvalParams {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ParamsError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    fn entries(&self) -> Vec<(String, &[Tapered])> {
+        let mut entries: Vec<(String, &[Tapered])> = vec![("piece_values".into(), &self.piece_values[..])];
+        for (name, table) in PIECE_NAMES.iter().zip(self.psqt.iter()) {
+            entries.push((format!("psqt.{}", name), &table[..]));
+        }
+        entries.extend(vec![
+            ("doubled_pawn".into(), slice::from_ref(&self.doubled_pawn)),
+            ("isolated_pawn".into(), slice::from_ref(&self.isolated_pawn)),
+            ("backward_pawn".into(), slice::from_ref(&self.backward_pawn)),
+            ("connected_pawn".into(), &self.connected_pawn[..]),
+            ("candidate_pawn".into(), &self.candidate_pawn[..]),
+            ("passed_pawn".into(), &self.passed_pawn[..]),
+            ("passed_free_path".into(), &self.passed_free_path[..]),
+            ("passed_own_king_distance".into(), slice::from_ref(&self.passed_own_king_distance)),
+            ("passed_enemy_king_distance".into(), slice::from_ref(&self.passed_enemy_king_distance)),
+            ("mobility.N".into(), &self.mobility_knight[..]),
+            ("mobility.B".into(), &self.mobility_bishop[..]),
+            ("mobility.R".into(), &self.mobility_rook[..]),
+            ("mobility.Q".into(), &self.mobility_queen[..]),
+            ("king_attack".into(), &self.king_attack[..]),
+            ("pawn_shelter".into(), &self.pawn_shelter[..]),
+            ("pawn_storm".into(), &self.pawn_storm[..]),
+        ]);
+        entries
+    }
+
+    fn entries_mut(&mut self) -> Vec<(String, &mut [Tapered])> {
+        let mut entries: Vec<(String, &mut [Tapered])> = vec![("piece_values".into(), &mut self.piece_values[..])];
+        for (name, table) in PIECE_NAMES.iter().zip(self.psqt.iter_mut()) {
+            entries.push((format!("psqt.{}", name), &mut table[..]));
+        }
+        entries.extend(vec![
+            ("doubled_pawn".into(), slice::from_mut(&mut self.doubled_pawn)),
+            ("isolated_pawn".into(), slice::from_mut(&mut self.isolated_pawn)),
+            ("backward_pawn".into(), slice::from_mut(&mut self.backward_pawn)),
+            ("connected_pawn".into(), &mut self.connected_pawn[..]),
+            ("candidate_pawn".into(), &mut self.candidate_pawn[..]),
+            ("passed_pawn".into(), &mut self.passed_pawn[..]),
+            ("passed_free_path".into(), &mut self.passed_free_path[..]),
+            ("passed_own_king_distance".into(), slice::from_mut(&mut self.passed_own_king_distance)),
+            ("passed_enemy_king_distance".into(), slice::from_mut(&mut self.passed_enemy_king_distance)),
+            ("mobility.N".into(), &mut self.mobility_knight[..]),
+            ("mobility.B".into(), &mut self.mobility_bishop[..]),
+            ("mobility.R".into(), &mut self.mobility_rook[..]),
+            ("mobility.Q".into(), &mut self.mobility_queen[..]),
+            ("king_attack".into(), &mut self.king_attack[..]),
+            ("pawn_shelter".into(), &mut self.pawn_shelter[..]),
+            ("pawn_storm".into(), &mut self.pawn_storm[..]),
+        ]);
+        entries
+    }
+
+    fn set(&mut self, key: &str, values: &[Tapered]) -> Result<(), ParamsError> {
+        let mut entries = self.entries_mut();
+        let (_, slot) = entries.iter_mut()
+            .find(|(name, _)| name == key)
+            .ok_or_else(|| ParamsError::UnknownKey(key.to_string()))?;
+
+        if slot.len() != values.len() {
+            return Err(ParamsError::Count { key: key.to_string(), expected: slot.len(), found: values.len() });
+        }
+        slot.copy_from_slice(values);
+        Ok(())
+    }
+}
+
+impl FromStr for EvalParams {
+    type Err = ParamsError;
+
+    fn from_str(s: &str) -> Result<Self, ParamsError> {
+        let mut params = EvalParams::default();
+        let mut key: Option<&str> = None;
+        let mut values = Vec::new();
+
+        let tokens = s.lines()
+            .map(|line| line.split('#').next().unwrap())
+            .flat_map(str::split_whitespace);
+        for token in tokens {
+            let starts_value = token.starts_with(|c: char| c.is_ascii_digit() || c == '-');
+            if !starts_value {
+                if let Some(key) = key {
+                    params.set(key, &values)?;
+                }
+                key = Some(token);
+                values.clear();
+                continue;
+            }
+
+            let current = key.ok_or_else(|| ParamsError::MissingKey(token.to_string()))?;
+            let value = parse_tapered(token).ok_or_else(|| ParamsError::Value {
+                key: current.to_string(),
+                value: token.to_string(),
+            })?;
+            values.push(value);
+        }
+        if let Some(key) = key {
+            params.set(key, &values)?;
+        }
+        Ok(params)
+    }
+}
+
+fn parse_tapered(token: &str) -> Option<Tapered> {
+    let (mg, eg) = token.split_once('/')?;
+    Some(Tapered::new(mg.parse().ok()?, eg.parse().ok()?))
+}
+
+/// Writes every parameter in the format `FromStr` reads, tables eight values to a line.
+impl fmt::Display for EvalParams {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (name, values) in self.entries() {
+            write!(f, "{}", name)?;
+            for (idx, value) in values.iter().enumerate() {
+                let separator = if idx % 8 == 0 && values.len() > 8 { "\n   " } else { " " };
+                write!(f, "{}{:>9}", separator, value.to_string())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        let mut psqt = [[Tapered::default(); 64]; PIECES_TYPE_COUNT];
+        for (piece, table) in psqt.iter_mut().enumerate() {
+            for (square, value) in table.iter_mut().enumerate() {
+                *value = Tapered::new(PSQT_MG[piece][square], PSQT_EG[piece][square]);
+            }
+        }
+
+        Self {
+            piece_values: [
+                Tapered::new(82, 94),
+                Tapered::new(337, 281),
+                Tapered::new(365, 297),
+                Tapered::new(477, 512),
+                Tapered::new(1025, 936),
+                Tapered::new(0, 0),
+            ],
+            psqt,
+
+            doubled_pawn: Tapered::new(-10, -25),
+            isolated_pawn: Tapered::new(-8, -15),
+            backward_pawn: Tapered::new(-8, -10),
+            connected_pawn: by_rank([(0, 0), (4, 2), (6, 4), (10, 8), (20, 18), (40, 40), (70, 70), (0, 0)]),
+            candidate_pawn: by_rank([(0, 0), (0, 3), (3, 6), (6, 12), (10, 20), (15, 30), (0, 0), (0, 0)]),
+            passed_pawn: by_rank([(0, 0), (0, 5), (0, 10), (5, 15), (15, 30), (30, 60), (50, 100), (0, 0)]),
+            passed_free_path: by_rank([(0, 0), (0, 0), (0, 0), (0, 5), (0, 10), (0, 20), (0, 40), (0, 0)]),
+            passed_own_king_distance: Tapered::new(0, -3),
+            passed_enemy_king_distance: Tapered::new(0, 5),
+
+            mobility_knight: linear(4, 4, 4),
+            mobility_bishop: linear(6, 5, 5),
+            mobility_rook: linear(7, 2, 4),
+            mobility_queen: linear(13, 1, 2),
+            king_attack: [
+                Tapered::new(0, 0),
+                Tapered::new(8, 2),
+                Tapered::new(6, 2),
+                Tapered::new(10, 2),
+                Tapered::new(12, 4),
+                Tapered::new(0, 0),
+            ],
+            pawn_shelter: by_rank([(-30, -5), (20, 5), (10, 3), (0, 0), (-5, 0), (-10, 0), (-10, 0), (0, 0)]),
+            pawn_storm: by_rank([(0, 0), (-10, -5), (-30, -10), (-20, -5), (-8, 0), (0, 0), (0, 0), (0, 0)]),
+        }
+    }
+}
+
+/// A table rising by `mg`/`eg` per step, crossing zero at `zero`.
+fn linear<const N: usize>(zero: i32, mg: i32, eg: i32) -> [Tapered; N] {
+    let mut table = [Tapered::default(); N];
+    for (idx, entry) in table.iter_mut().enumerate() {
+        *entry = Tapered::new(mg, eg) * (idx as i32 - zero);
+    }
+    table
+}
+
+fn by_rank(values: [(i32, i32); 8]) -> [Tapered; 8] {
+    let mut table = [Tapered::default(); 8];
+    for (entry, (mg, eg)) in table.iter_mut().zip(values.iter()) {
+        *entry = Tapered::new(*mg, *eg);
+    }
+    table
+}
+
+// Default piece-square tables, rank 8 first as seen from White
+const PSQT_MG: [[i32; 64]; PIECES_TYPE_COUNT] = [
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+         98, 134,  61,  95,  68, 126,  34, -11,
+         -6,   7,  26,  31,  65,  56,  25, -20,
+        -14,  13,   6,  21,  23,  12,  17, -23,
+        -27,  -2,  -5,  12,  17,   6,  10, -25,
+        -26,  -4,  -4, -10,   3,   3,  33, -12,
+        -35,  -1, -20, -23, -15,  24,  38, -22,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [
+       -167, -89, -34, -49,  61, -97, -15,-107,
+        -73, -41,  72,  36,  23,  62,   7, -17,
+        -47,  60,  37,  65,  84, 129,  73,  44,
+         -9,  17,  19,  53,  37,  69,  18,  22,
+        -13,   4,  16,  13,  28,  19,  21,  -8,
+        -23,  -9,  12,  10,  19,  17,  25, -16,
+        -29, -53, -12,  -3,  -1,  18, -14, -19,
+       -105, -21, -58, -33, -17, -28, -19, -23,
+    ],
+    [
+        -29,   4, -82, -37, -25, -42,   7,  -8,
+        -26,  16, -18, -13,  30,  59,  18, -47,
+        -16,  37,  43,  40,  35,  50,  37,  -2,
+         -4,   5,  19,  50,  37,  37,   7,  -2,
+         -6,  13,  13,  26,  34,  12,  10,   4,
+          0,  15,  15,  15,  14,  27,  18,  10,
+          4,  15,  16,   0,   7,  21,  33,   1,
+        -33,  -3, -14, -21, -13, -12, -39, -21,
+    ],
+    [
+         32,  42,  32,  51,  63,   9,  31,  43,
+         27,  32,  58,  62,  80,  67,  26,  44,
+         -5,  19,  26,  36,  17,  45,  61,  16,
+        -24, -11,   7,  26,  24,  35,  -8, -20,
+        -36, -26, -12,  -1,   9,  -7,   6, -23,
+        -45, -25, -16, -17,   3,   0,  -5, -33,
+        -44, -16, -20,  -9,  -1,  11,  -6, -71,
+        -19, -13,   1,  17,  16,   7, -37, -26,
+    ],
+    [
+        -28,   0,  29,  12,  59,  44,  43,  45,
+        -24, -39,  -5,   1, -16,  57,  28,  54,
+        -13, -17,   7,   8,  29,  56,  47,  57,
+        -27, -27, -16, -16,  -1,  17,  -2,   1,
+         -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+        -14,   2, -11,  -2,  -5,   2,  14,   5,
+        -35,  -8,  11,   2,   8,  15,  -3,   1,
+         -1, -18,  -9,  10, -15, -25, -31, -50,
+    ],
+    [
+        -65,  23,  16, -15, -56, -34,   2,  13,
+         29,  -1, -20,  -7,  -8,  -4, -38, -29,
+         -9,  24,   2, -16, -20,   6,  22, -22,
+        -17, -20, -12, -27, -30, -25, -14, -36,
+        -49,  -1, -27, -39, -46, -44, -33, -51,
+        -14, -14, -22, -46, -44, -30, -15, -27,
+          1,   7,  -8, -64, -43, -16,   9,   8,
+        -15,  36,  12, -54,   8, -28,  24,  14,
+    ],
+];
+
+const PSQT_EG: [[i32; 64]; PIECES_TYPE_COUNT] = [
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+        178, 173, 158, 134, 147, 132, 165, 187,
+         94, 100,  85,  67,  56,  53,  82,  84,
+         32,  24,  13,   5,  -2,   4,  17,  17,
+         13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+          4,   7,  -6,   1,   0,  -5,  -1,  -8,
+         13,   8,   8,  10,  13,   0,   2,  -7,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [
+        -58, -38, -13, -28, -31, -27, -63, -99,
+        -25,  -8, -25,  -2,  -9, -25, -24, -52,
+        -24, -20,  10,   9,  -1,  -9, -19, -41,
+        -17,   3,  22,  22,  22,  11,   8, -18,
+        -18,  -6,  16,  25,  16,  17,   4, -18,
+        -23,  -3,  -1,  15,  10,  -3, -20, -22,
+        -42, -20, -10,  -5,  -2, -20, -23, -44,
+        -29, -51, -23, -15, -22, -18, -50, -64,
+    ],
+    [
+        -14, -21, -11,  -8,  -7,  -9, -17, -24,
+         -8,  -4,   7, -12,  -3, -13,  -4, -14,
+          2,  -8,   0,  -1,  -2,   6,   0,   4,
+         -3,   9,  12,   9,  14,  10,   3,   2,
+         -6,   3,  13,  19,   7,  10,  -3,  -9,
+        -12,  -3,   8,  10,  13,   3,  -7, -15,
+        -14, -18,  -7,  -1,   4,  -9, -15, -27,
+        -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+    ],
+    [
+         13,  10,  18,  15,  12,  12,   8,   5,
+         11,  13,  13,  11,  -3,   3,   8,   3,
+          7,   7,   7,   5,   4,  -3,  -5,  -3,
+          4,   3,  13,   1,   2,   1,  -1,   2,
+          3,   5,   8,   4,  -5,  -6,  -8, -11,
+         -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+         -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+         -9,   2,   3,  -1,  -5, -13,   4, -20,
+    ],
+    [
+         -9,  22,  22,  27,  27,  19,  10,  20,
+        -17,  20,  32,  41,  58,  25,  30,   0,
+        -20,   6,   9,  49,  47,  35,  19,   9,
+          3,  22,  24,  45,  57,  40,  57,  36,
+        -18,  28,  19,  47,  31,  34,  39,  23,
+        -16, -27,  15,   6,   9,  17,  10,   5,
+        -22, -23, -30, -16, -16, -23, -36, -32,
+        -33, -28, -22, -43,  -5, -32, -20, -41,
+    ],
+    [
+        -74, -35, -18, -18, -11,  15,   4, -17,
+        -12,  17,  14,  17,  17,  38,  23,  11,
+         10,  17,  23,  15,  20,  45,  44,  13,
+         -8,  22,  24,  27,  26,  33,  26,   3,
+        -18,  -4,  21,  24,  27,  23,   9, -11,
+        -19,  -3,  11,  21,  23,  16,   7,  -9,
+        -27, -11,   4,  13,  14,   4,  -5, -17,
+        -53, -34, -21, -11, -28, -14, -24, -43,
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::eval::Tapered;
+    use crate::eval::params::{EvalParams, ParamsError};
+
+    #[test]
+    fn params_round_trip() {
+        let params = EvalParams::default();
+        let text = params.to_string();
+        assert!(text.starts_with("piece_values"));
+        assert_eq!(text.parse::<EvalParams>().unwrap(), params);
+    }
+
+    #[test]
+    fn params_partial_override() {
+        let params: EvalParams = "# tuned\npiece_values 100/100 300/300 300/300 500/500 900/900 0/0\n".parse().unwrap();
+        assert_eq!(params.piece_values[1], Tapered::new(300, 300));
+        assert_eq!(params.psqt, EvalParams::default().psqt);
+
+        let params: EvalParams = "isolated_pawn -20/-30".parse().unwrap();
+        assert_eq!(params.isolated_pawn, Tapered::new(-20, -30));
+    }
+
+    #[test]
+    fn params_errors() {
+        assert!(matches!("bogus 1/2".parse::<EvalParams>(), Err(ParamsError::UnknownKey(_))));
+        assert!(matches!("piece_values 1/2".parse::<EvalParams>(), Err(ParamsError::Count { expected: 6, found: 1, .. })));
+        assert!(matches!("isolated_pawn 1/2 3/4".parse::<EvalParams>(), Err(ParamsError::Count { expected: 1, found: 2, .. })));
+        assert!(matches!("piece_values 1,2".parse::<EvalParams>(), Err(ParamsError::Value { .. })));
+        assert!(matches!("piece_values 1/".parse::<EvalParams>(), Err(ParamsError::Value { .. })));
+        assert!(matches!("12/3".parse::<EvalParams>(), Err(ParamsError::MissingKey(_))));
+    }
+}
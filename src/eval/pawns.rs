@@ -0,0 +1,301 @@
+use crate::board::Board;
+use crate::board_representation::bitboard::BitBoard;
+use crate::board_representation::bitboard::files_ranks::{ADJACENT_FILE_BITBOARDS, FILE_BITBOARDS};
+use crate::board_representation::bitboard::shift::Direction;
+use crate::board_representation::square::Square;
+use crate::eval::Tapered;
+use crate::eval::params::EvalParams;
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+
+const PAWN_TABLE_ENTRIES: usize = 1 << 14;
+
+/// What is known about a pawn structure: the pawn-only terms, and the passed pawns that the
+/// king-dependent terms are computed from at every evaluation.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PawnEntry {
+    key: u64,
+    /// From White's point of view
+    pub score: Tapered,
+    pub passed: [BitBoard; 2],
+}
+
+/// A direct-mapped cache of pawn structure evaluations, keyed on `Board::pawn_hash`. Pawn
+/// structures change rarely along a search, so nearly every probe hits.
+pub struct PawnTable {
+    entries: Vec<PawnEntry>,
+}
+
+impl PawnTable {
+    pub fn new() -> Self {
+        // The zeroed entries are correct for the (key 0) structure without any pawns
+        Self { entries: vec![PawnEntry::default(); PAWN_TABLE_ENTRIES] }
+    }
+
+    pub fn probe(&mut self, board: &Board, params: &EvalParams) -> PawnEntry {
+        let key = board.pawn_hash();
+        let entry = &mut self.entries[key as usize % PAWN_TABLE_ENTRIES];
+        if entry.key != key {
+            *entry = evaluate_pawns(board, params);
+            entry.key = key;
+        }
+        *entry
+    }
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Evaluates both sides' pawn structures from scratch.
+pub fn evaluate_pawns(board: &Board, params: &EvalParams) -> PawnEntry {
+    let (white, white_passed) = evaluate_side(board, params, Colour::White);
+    let (black, black_passed) = evaluate_side(board, params, Colour::Black);
+    PawnEntry {
+        key: board.pawn_hash(),
+        score: white - black,
+        passed: [white_passed, black_passed],
+    }
+}
+
+fn evaluate_side(board: &Board, params: &EvalParams, us: Colour) -> (Tapered, BitBoard) {
+    let them = us.opposite();
+    let ours = board.bb_pieces_of(us, PieceType::P);
+    let theirs = board.bb_pieces_of(them, PieceType::P);
+    let up = forward(us);
+
+    let mut score = Tapered::default();
+    let mut passed: BitBoard = 0.into();
+
+    for square in ours.iter_squares() {
+        let bb = BitBoard::from(square);
+        let rank = relative_rank(square, us);
+        let file = FILE_BITBOARDS[square.file() as usize];
+        let adjacent = ADJACENT_FILE_BITBOARDS[square.file() as usize];
+        let sideways = bb.shift(Direction::East) | bb.shift(Direction::West);
+
+        let ahead = front_span(bb, us);
+        let ahead_adjacent = front_span(sideways, us);
+        // Own pawns on neighbouring files that are level or behind, which could defend it
+        let helpers = ours & adjacent & !ahead_adjacent;
+
+        let doubled = !(ours & ahead).is_empty();
+        let isolated = (ours & adjacent).is_empty();
+        let opposed = !(theirs & ahead & file).is_empty();
+        let is_passed = !doubled && (theirs & (ahead | ahead_adjacent)).is_empty();
+        let supported = !(PieceType::pawn_attack(bb, them) & ours).is_empty();
+        let phalanx = !(sideways & ours).is_empty();
+
+        if doubled {
+            score += params.doubled_pawn;
+        }
+        if isolated {
+            score += params.isolated_pawn;
+        }
+        else if helpers.is_empty() && !(PieceType::pawn_attack(bb.shift(up), us) & theirs).is_empty() {
+            // Backward: no neighbour can come up to defend it, and it can't safely advance
+            score += params.backward_pawn;
+        }
+        if supported || phalanx {
+            score += params.connected_pawn[rank];
+        }
+
+        if is_passed {
+            score += params.passed_pawn[rank];
+            passed |= bb;
+        }
+        else if !opposed && helpers.count() >= (theirs & ahead_adjacent).count() {
+            // Candidate: can become passed by trading its helpers for the sentries
+            score += params.candidate_pawn[rank];
+        }
+    }
+    (score, passed)
+}
+
+/// Passed pawn terms that depend on more than the pawns: how close each king is to the
+/// square in front of the pawn, and whether its path to promotion is clear. From `us`'s
+/// point of view.
+pub fn evaluate_passed(board: &Board, params: &EvalParams, passed: BitBoard, us: Colour) -> Tapered {
+    let mut score = Tapered::default();
+    let occupancy = board.occupancy();
+    let own_king = board.bb_pieces_of(us, PieceType::K).iter_squares().next();
+    let enemy_king = board.bb_pieces_of(us.opposite(), PieceType::K).iter_squares().next();
+
+    for square in passed.iter_squares() {
+        let rank = relative_rank(square, us);
+        let bb = BitBoard::from(square);
+        // A pawn on its last rank, as a FEN may set up, has nowhere to go
+        let stop = match bb.shift(forward(us)).iter_squares().next() {
+            Some(stop) => stop,
+            None => continue,
+        };
+
+        // King proximity only matters once the pawn is on its way
+        let weight = rank.saturating_sub(2) as i32;
+        if let (Some(own), Some(enemy)) = (own_king, enemy_king) {
+            score += params.passed_own_king_distance * (own.distance(stop) as i32 * weight);
+            score += params.passed_enemy_king_distance * (enemy.distance(stop) as i32 * weight);
+        }
+        if (front_span(bb, us) & occupancy).is_empty() {
+            score += params.passed_free_path[rank];
+        }
+    }
+    score
+}
+
+/// The squares in front of `bb`'s squares, from `colour`'s point of view.
+fn front_span(bb: BitBoard, colour: Colour) -> BitBoard {
+    let up = forward(colour);
+    let mut span = bb.shift(up);
+    for _ in 0..5 {
+        span |= span.shift(up);
+    }
+    span
+}
+
+fn forward(colour: Colour) -> Direction {
+    match colour {
+        Colour::White => Direction::North,
+        Colour::Black => Direction::South,
+    }
+}
+
+/// The rank counted from `colour`'s side: 0 for its back rank, 7 for the promotion rank.
+pub fn relative_rank(square: Square, colour: Colour) -> usize {
+    match colour {
+        Colour::White => square.rank() as usize,
+        Colour::Black => 7 - square.rank() as usize,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::fen::STARTING_FEN;
+    use crate::eval::Tapered;
+    use crate::eval::params::EvalParams;
+    use crate::eval::pawns::{evaluate_passed, evaluate_pawns, PawnTable};
+    use crate::piece::colour::Colour;
+
+    fn pawns(fen: &str) -> Tapered {
+        pawns_with(fen, &EvalParams::default())
+    }
+
+    /// Params with every pawn term zero except `set`'s.
+    fn only(set: fn(&mut EvalParams)) -> EvalParams {
+        let mut params = EvalParams {
+            doubled_pawn: Tapered::default(),
+            isolated_pawn: Tapered::default(),
+            backward_pawn: Tapered::default(),
+            connected_pawn: [Tapered::default(); 8],
+            candidate_pawn: [Tapered::default(); 8],
+            passed_pawn: [Tapered::default(); 8],
+            ..EvalParams::default()
+        };
+        set(&mut params);
+        params
+    }
+
+    fn pawns_with(fen: &str, params: &EvalParams) -> Tapered {
+        let board: Board = fen.parse().unwrap();
+        evaluate_pawns(&board, params).score
+    }
+
+    fn term(fen: &str, params: &EvalParams) -> i32 {
+        let board: Board = fen.parse().unwrap();
+        evaluate_pawns(&board, params).score.mg
+    }
+
+    #[test]
+    fn pawns_symmetric() {
+        assert_eq!(pawns(STARTING_FEN), Tapered::default());
+        assert_eq!(pawns("4k3/pp3p1p/2p3p1/8/8/2P3P1/PP3P1P/4K3 w - - 0 1"), Tapered::default());
+    }
+
+    #[test]
+    fn pawns_doubled_and_isolated() {
+        let doubled = only(|p| p.doubled_pawn = Tapered::new(1, 0));
+        // Only the rear pawn of a doubled pair counts
+        assert_eq!(term("4k3/8/8/8/4P3/4P3/8/4K3 w - - 0 1", &doubled), 1);
+        assert_eq!(term("4k3/8/8/8/4P3/4P3/4P3/4K3 w - - 0 1", &doubled), 2);
+
+        let isolated = only(|p| p.isolated_pawn = Tapered::new(1, 0));
+        assert_eq!(term("4k3/8/8/8/8/8/P1P1P3/4K3 w - - 0 1", &isolated), 3);
+        assert_eq!(term("4k3/8/8/8/8/8/PP2P3/4K3 w - - 0 1", &isolated), 1);
+        assert_eq!(term("4k3/p7/8/8/8/8/PP2P3/4K3 w - - 0 1", &isolated), 0);
+    }
+
+    #[test]
+    fn pawns_backward() {
+        let backward = only(|p| p.backward_pawn = Tapered::new(1, 0));
+        // d3 can't be defended by c4 and e4, and d4 is covered by the pawn on e5
+        assert_eq!(term("4k3/8/8/4p3/2P1P3/3P4/8/4K3 w - - 0 1", &backward), 1);
+        // Without the enemy pawn it may just advance
+        assert_eq!(term("4k3/8/8/8/2P1P3/3P4/8/4K3 w - - 0 1", &backward), 0);
+    }
+
+    #[test]
+    fn pawns_passed_and_candidates() {
+        let passed = only(|p| p.passed_pawn = [Tapered::new(1, 0); 8]);
+        assert_eq!(term("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1", &passed), 1);
+        assert_eq!(term("4k3/4p3/8/3P4/8/8/8/4K3 w - - 0 1", &passed), 0);
+        assert_eq!(term("4k3/8/8/3P4/4p3/8/8/4K3 w - - 0 1", &passed), 0);
+        // A black passer counts against White
+        assert_eq!(term("4k3/8/8/8/8/8/p7/4K3 w - - 0 1", &passed), -1);
+
+        let board: Board = "4k3/8/8/3P4/8/8/p7/4K3 w - - 0 1".parse().unwrap();
+        let entry = evaluate_pawns(&board, &EvalParams::default());
+        assert_eq!(entry.passed[0].count(), 1);
+        assert_eq!(entry.passed[1].count(), 1);
+
+        let candidate = only(|p| p.candidate_pawn = [Tapered::new(1, 0); 8]);
+        // c4 and d4 against d6: d4 is opposed, but c4 can become passed with d4's help
+        assert_eq!(term("4k3/8/3p4/8/2PP4/8/8/4K3 w - - 0 1", &candidate), 1);
+        // One helper against two sentries is not enough
+        assert_eq!(term("4k3/8/2p1p3/8/3P4/8/8/4K3 w - - 0 1", &candidate), 0);
+    }
+
+    #[test]
+    fn pawns_on_last_rank() {
+        // FEN accepts pawns on the back ranks; they count as passed but have no stop square,
+        // so they get the rank bonus and none of the terms measured from the stop square
+        let params = only(|p| {
+            p.passed_pawn[7] = Tapered::new(5, 7);
+            p.passed_own_king_distance = Tapered::new(1, 1);
+            p.passed_enemy_king_distance = Tapered::new(1, 1);
+            p.passed_free_path = [Tapered::new(1, 1); 8];
+        });
+        assert_eq!(pawns_with("3Pk3/8/8/8/8/8/8/4K3 w - - 0 1", &params), Tapered::new(5, 7));
+        assert_eq!(pawns_with("4k3/8/8/8/8/8/8/1p2K3 w - - 0 1", &params), Tapered::new(-5, -7));
+
+        let board: Board = "3Pk3/8/8/8/8/8/8/1p2K3 w - - 0 1".parse().unwrap();
+        let entry = evaluate_pawns(&board, &params);
+        for colour in [Colour::White, Colour::Black].iter() {
+            let passed = entry.passed[*colour as usize];
+            assert_eq!(passed.count(), 1);
+            assert_eq!(evaluate_passed(&board, &params, passed, *colour), Tapered::default());
+        }
+    }
+
+    #[test]
+    fn pawns_connected() {
+        let connected = only(|p| p.connected_pawn = [Tapered::new(1, 0); 8]);
+        // Phalanx on d4/e4, and f3 defending e4
+        assert_eq!(term("4k3/8/8/8/3PP3/5P2/8/4K3 w - - 0 1", &connected), 2);
+        assert_eq!(term("4k3/8/8/8/3P4/8/5P2/4K3 w - - 0 1", &connected), 0);
+    }
+
+    #[test]
+    fn pawn_table_hits() {
+        let params = EvalParams::default();
+        let mut table = PawnTable::new();
+        let a: Board = "4k3/pp6/8/8/8/8/PPP5/4K3 w - - 0 1".parse().unwrap();
+        let b: Board = "r3k3/pp6/8/8/8/8/PPP5/R3K3 b - - 0 1".parse().unwrap();
+
+        assert_eq!(a.pawn_hash(), b.pawn_hash());
+        assert_eq!(table.probe(&a, &params).score, table.probe(&b, &params).score);
+        assert_eq!(table.probe(&a, &params).score, evaluate_pawns(&a, &params).score);
+    }
+}
@@ -1,4 +1,11 @@
 pub mod board;
+pub mod book;
 pub mod board_representation;
 pub mod common;
-pub mod piece;
\ No newline at end of file
+pub mod eval;
+pub mod pgn;
+pub mod piece;
+pub mod perft;
+pub mod search;
+#[cfg(test)]
+mod testing;
\ No newline at end of file
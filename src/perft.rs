@@ -0,0 +1,138 @@
+use crate::board::Board;
+use crate::board::moves::{Move, MoveList};
+
+/// Counts the leaf nodes of the legal move tree to `depth` plies.
+///
+/// Moves at the last ply are counted rather than played ("bulk counting"), which is only
+/// valid because `generate_legal` emits strictly legal moves.
+pub fn perft(board: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut moves = MoveList::new();
+    board.generate_legal(&mut moves);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for mv in moves.iter() {
+        let undo = board.make_move(*mv);
+        nodes += perft(board, depth - 1);
+        board.unmake_move(*mv, undo);
+    }
+    nodes
+}
+
+/// Like `perft`, but reports the node count below each root move separately, which is
+/// the usual way of narrowing down a move generation bug against a reference engine.
+pub fn perft_divide(board: &mut Board, depth: u32) -> Vec<(Move, u64)> {
+    let mut moves = MoveList::new();
+    board.generate_legal(&mut moves);
+
+    moves.iter()
+        .map(|mv| {
+            let undo = board.make_move(*mv);
+            let nodes = if depth > 1 { perft(board, depth - 1) } else { 1 };
+            board.unmake_move(*mv, undo);
+            (*mv, nodes)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::perft::{perft, perft_divide};
+
+    fn check(fen: &str, expected: &[u64]) {
+        let mut board: Board = fen.parse().unwrap();
+        for (depth, nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&mut board, depth as u32 + 1), *nodes, "{} at depth {}", fen, depth + 1);
+        }
+    }
+
+    #[test]
+    fn perft_starting() {
+        check(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8_902, 197_281, 4_865_609],
+        );
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        check(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2_039, 97_862, 4_085_603],
+        );
+    }
+
+    #[test]
+    fn perft_position_3() {
+        check(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2_812, 43_238, 674_624, 11_030_083],
+        );
+    }
+
+    #[test]
+    fn perft_position_4() {
+        check(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9_467, 422_333, 15_833_292],
+        );
+    }
+
+    #[test]
+    fn perft_position_4_mirrored() {
+        check(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            &[6, 264, 9_467, 422_333],
+        );
+    }
+
+    #[test]
+    fn perft_position_5() {
+        check(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1_486, 62_379, 2_103_487],
+        );
+    }
+
+    #[test]
+    fn perft_position_6() {
+        check(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2_079, 89_890, 3_894_594],
+        );
+    }
+
+    #[test]
+    fn perft_chess960() {
+        // Positions from the Chess960 perft suite, with castling rooks off the a and h files
+        // and kings and rooks that land on each other's squares
+        let positions: [(&str, [u64; 4]); 5] = [
+            ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", [21, 528, 12_189, 326_672]),
+            ("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", [21, 807, 18_002, 667_366]),
+            ("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", [20, 479, 10_471, 273_318]),
+            ("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", [22, 593, 13_440, 382_958]),
+            ("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", [28, 1_120, 31_058, 1_171_749]),
+        ];
+        for (fen, expected) in positions.iter() {
+            check(fen, expected);
+        }
+    }
+
+    #[test]
+    fn perft_divide_sums_to_perft() {
+        let mut board: Board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        let divide = perft_divide(&mut board, 3);
+
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_, n)| n).sum::<u64>(), 97_862);
+        let castle = divide.iter().find(|(mv, _)| mv.to_string() == "e1g1").unwrap();
+        assert_eq!(castle.1, 2_059);
+    }
+}
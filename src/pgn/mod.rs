@@ -0,0 +1,213 @@
+use std::fmt::Write as _;
+use std::io;
+use std::time::Duration;
+use crate::board::Board;
+use crate::board::fen;
+use crate::board::fen::STARTING_FEN;
+use crate::board::moves::Move;
+use crate::board::outcome::GameResult;
+use crate::board::san::SanError;
+use crate::search::Score;
+
+pub mod reader;
+pub mod writer;
+
+/// The tags every exported game carries, in the order they are written.
+pub const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum PgnError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid FEN tag: {0}")]
+    Fen(#[from] fen::ParseError),
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+    #[error("line {line}: {san}: {source}")]
+    San { line: usize, san: String, source: SanError },
+}
+
+/// A move of a game together with everything annotating it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnMove {
+    pub mv: Move,
+    /// Numeric annotation glyphs, e.g. 1 for `!` and 4 for `??`
+    pub nags: Vec<u8>,
+    /// Comments written before the move, at the start of a line or after the variations
+    /// of the previous move
+    pub comments_before: Vec<String>,
+    /// Comments written after the move, with `[%clk]` and `[%eval]` taken out
+    pub comments: Vec<String>,
+    /// Time left on the mover's clock after the move, from `[%clk]`
+    pub clock: Option<Duration>,
+    /// Evaluation after the move, from `[%eval]`, from White's point of view
+    pub eval: Option<Score>,
+    /// Alternatives to this move, each played from the position before it
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    pub fn new(mv: Move) -> Self {
+        Self {
+            mv,
+            nags: Vec::new(),
+            comments_before: Vec::new(),
+            comments: Vec::new(),
+            clock: None,
+            eval: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+/// A game read from or to be written to PGN.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Game {
+    /// Tag pairs in the order they were read
+    pub tags: Vec<(String, String)>,
+    /// The main line
+    pub moves: Vec<PgnMove>,
+    /// `None` for a game in progress or of unknown result (`*`)
+    pub result: Option<GameResult>,
+}
+
+impl Game {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Sets the value of a tag, adding it after the existing tags if it is new.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// The position the game starts from: the `FEN` tag if there is one, otherwise the
+    /// standard starting position.
+    pub fn start_position(&self) -> Result<Board, PgnError> {
+        Ok(self.tag("FEN").unwrap_or(STARTING_FEN).parse()?)
+    }
+
+    /// The moves of the main line, without annotations.
+    pub fn mainline(&self) -> impl Iterator<Item = Move> + '_ {
+        self.moves.iter().map(|m| m.mv)
+    }
+}
+
+/// Splits the `[%clk ...]` and `[%eval ...]` commands out of a comment, returning them
+/// with the remaining text. Other commands, and commands that fail to parse, stay in the
+/// text.
+pub(crate) fn parse_annotations(comment: &str) -> (Option<Duration>, Option<Score>, String) {
+    let mut clock = None;
+    let mut eval = None;
+    let mut text = String::new();
+    let mut rest = comment;
+
+    while let Some(start) = rest.find("[%") {
+        let end = match rest[start..].find(']') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let command = &rest[start + 2..end];
+        let (name, value) = command.split_at(command.find(char::is_whitespace).unwrap_or(command.len()));
+        let parsed = match name {
+            "clk" => parse_clock(value.trim()).map(|c| clock = Some(c)),
+            "eval" => parse_eval(value.trim()).map(|e| eval = Some(e)),
+            _ => None,
+        };
+
+        text.push_str(&rest[..start]);
+        if parsed.is_none() {
+            text.push_str(&rest[start..=end]);
+        }
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+
+    (clock, eval, text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Reads a clock in `h:mm:ss` form, with optional fractions of a second.
+fn parse_clock(s: &str) -> Option<Duration> {
+    let mut seconds = 0.0;
+    for part in s.split(':') {
+        let value: f64 = part.parse().ok()?;
+        if !value.is_finite() || value < 0.0 {
+            return None;
+        }
+        seconds = seconds * 60.0 + value;
+    }
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Reads an evaluation in pawns (`0.17`) or as a mate distance (`#-3`).
+fn parse_eval(s: &str) -> Option<Score> {
+    match s.strip_prefix('#') {
+        Some(mate) => mate.parse().ok().map(Score::Mate),
+        None => s.parse::<f64>().ok().filter(|p| p.is_finite()).map(|p| Score::Centipawns((p * 100.0).round() as i32)),
+    }
+}
+
+pub(crate) fn format_clock(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    let mut s = format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    let tenths = clock.subsec_millis() / 100;
+    if tenths != 0 {
+        write!(s, ".{}", tenths).unwrap();
+    }
+    s
+}
+
+pub(crate) fn format_eval(eval: Score) -> String {
+    match eval {
+        Score::Centipawns(cp) => format!("{:.2}", cp as f64 / 100.0),
+        Score::Mate(n) => format!("#{}", n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::pgn::{format_clock, format_eval, parse_annotations, Game};
+    use crate::search::Score;
+
+    #[test]
+    fn annotations() {
+        let (clock, eval, text) = parse_annotations("[%eval 0.17] [%clk 0:03:12] A good move");
+        assert_eq!(clock, Some(Duration::from_secs(192)));
+        assert_eq!(eval, Some(Score::Centipawns(17)));
+        assert_eq!(text, "A good move");
+
+        let (clock, eval, text) = parse_annotations("[%eval #-3][%clk 1:00:00.5]");
+        assert_eq!(clock, Some(Duration::from_millis(3_600_500)));
+        assert_eq!(eval, Some(Score::Mate(-3)));
+        assert_eq!(text, "");
+
+        // Unknown or broken commands are left in the text
+        let (clock, eval, text) = parse_annotations("[%csl Ga4] [%clk soon] [%eval");
+        assert_eq!((clock, eval), (None, None));
+        assert_eq!(text, "[%csl Ga4] [%clk soon] [%eval");
+    }
+
+    #[test]
+    fn annotation_formatting() {
+        assert_eq!(format_clock(Duration::from_secs(192)), "0:03:12");
+        assert_eq!(format_clock(Duration::from_millis(3_600_500)), "1:00:00.5");
+        assert_eq!(format_eval(Score::Centipawns(17)), "0.17");
+        assert_eq!(format_eval(Score::Centipawns(-150)), "-1.50");
+        assert_eq!(format_eval(Score::Mate(-3)), "#-3");
+    }
+
+    #[test]
+    fn tags() {
+        let mut game = Game::default();
+        assert_eq!(game.tag("White"), None);
+        game.set_tag("White", "Carlsen");
+        game.set_tag("Black", "Nakamura");
+        game.set_tag("White", "Caruana");
+        assert_eq!(game.tag("White"), Some("Caruana"));
+        assert_eq!(game.tags.len(), 2);
+    }
+}
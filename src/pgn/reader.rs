@@ -0,0 +1,557 @@
+use std::io::BufRead;
+use std::mem;
+use crate::board::Board;
+use crate::board::make_move::Undo;
+use crate::board::moves::Move;
+use crate::board::outcome::GameResult;
+use crate::board::san::SanError;
+use crate::pgn::{parse_annotations, Game, PgnError, PgnMove};
+
+/// Reads games one at a time from PGN text. Only the line being parsed is held in
+/// memory, so databases of any size can be streamed.
+///
+/// Text that is not valid UTF-8 is read lossily. After a game fails to parse, the reader
+/// skips ahead to the next line starting with a tag, so one broken game does not end the
+/// stream.
+pub struct PgnReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    line: String,
+    pos: usize,
+    line_number: usize,
+    in_movetext: bool,
+    failed: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            line: String::new(),
+            pos: 0,
+            line_number: 0,
+            in_movetext: false,
+            failed: false,
+        }
+    }
+
+    /// Reads the next game, or `None` at the end of the input.
+    pub fn read_game(&mut self) -> Result<Option<Game>, PgnError> {
+        let result = self.parse_game();
+        if let Err(e) = &result {
+            if !matches!(e, PgnError::Io(_)) {
+                self.skip_rest_of_game()?;
+            }
+        }
+        result
+    }
+
+    fn parse_game(&mut self) -> Result<Option<Game>, PgnError> {
+        let mut game = Game::default();
+
+        self.in_movetext = false;
+        loop {
+            match self.skip_whitespace()? {
+                Some('[') => {
+                    self.bump();
+                    let tag = self.read_tag()?;
+                    game.tags.push(tag);
+                }
+                None if game.tags.is_empty() => return Ok(None),
+                _ => break,
+            }
+        }
+
+        self.in_movetext = true;
+        let mut movetext = Movetext::new(game.start_position()?);
+        game.result = loop {
+            let c = match self.skip_whitespace()? {
+                Some(c) => c,
+                None => break None,
+            };
+            match c {
+                '{' => {
+                    self.bump();
+                    let text = self.read_comment()?;
+                    movetext.comment(&text);
+                }
+                ';' => {
+                    let text = self.line[self.pos + 1..].trim_end().to_string();
+                    self.pos = self.line.len();
+                    movetext.comment(&text);
+                }
+                '(' => {
+                    self.bump();
+                    movetext.open().map_err(|message| self.syntax(message))?;
+                }
+                ')' => {
+                    self.bump();
+                    movetext.close().map_err(|message| self.syntax(message))?;
+                }
+                '$' => {
+                    self.bump();
+                    let digits = self.read_symbol();
+                    let nag = digits.parse().map_err(|_| self.syntax(format!("invalid NAG: ${}", digits)))?;
+                    movetext.nag(nag);
+                }
+                '!' | '?' => {
+                    let suffix = self.read_while(|c| c == '!' || c == '?');
+                    let nag = match suffix.as_str() {
+                        "!" => 1,
+                        "?" => 2,
+                        "!!" => 3,
+                        "??" => 4,
+                        "!?" => 5,
+                        "?!" => 6,
+                        _ => return Err(self.syntax(format!("invalid annotation: {}", suffix))),
+                    };
+                    movetext.nag(nag);
+                }
+                '.' => self.bump(),
+                '*' => {
+                    self.bump();
+                    break None;
+                }
+                // The next game's tags, after a game without a result
+                '[' => break None,
+                c if is_symbol_char(c) => {
+                    let symbol = self.read_symbol();
+                    match symbol.as_str() {
+                        "1-0" => break Some(GameResult::WhiteWins),
+                        "0-1" => break Some(GameResult::BlackWins),
+                        "1/2-1/2" => break Some(GameResult::Draw),
+                        // Move numbers carry no information
+                        _ if symbol.bytes().all(|b| b.is_ascii_digit()) => {}
+                        _ => movetext.play(&symbol).map_err(|source| PgnError::San {
+                            line: self.line_number,
+                            san: symbol,
+                            source,
+                        })?,
+                    }
+                }
+                c => return Err(self.syntax(format!("unexpected character: {:?}", c))),
+            }
+        };
+
+        game.moves = movetext.finish().map_err(|message| self.syntax(message))?;
+        Ok(Some(game))
+    }
+
+    /// Reads the rest of a `[Name "value"]` tag pair after the opening bracket.
+    fn read_tag(&mut self) -> Result<(String, String), PgnError> {
+        self.skip_whitespace()?;
+        let name = self.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        if name.is_empty() {
+            return Err(self.syntax("expected a tag name"));
+        }
+
+        if self.skip_whitespace()? != Some('"') {
+            return Err(self.syntax(format!("expected a value for tag {}", name)));
+        }
+        self.bump();
+        let mut value = String::new();
+        loop {
+            match self.peek()? {
+                Some('"') => break,
+                Some('\\') => {
+                    self.bump();
+                    if let Some(c) = self.peek()? {
+                        value.push(c);
+                    }
+                }
+                Some(c) => value.push(c),
+                None => return Err(self.syntax("unterminated tag value")),
+            }
+            self.bump();
+        }
+        self.bump();
+
+        if self.skip_whitespace()? != Some(']') {
+            return Err(self.syntax(format!("expected ']' after tag {}", name)));
+        }
+        self.bump();
+        Ok((name, value))
+    }
+
+    /// Reads the rest of a `{}` comment after the opening brace, which may span lines.
+    fn read_comment(&mut self) -> Result<String, PgnError> {
+        let start = self.line_number;
+        let mut text = String::new();
+        loop {
+            match self.peek()? {
+                Some('}') => {
+                    self.bump();
+                    return Ok(text);
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.bump();
+                }
+                None => return Err(PgnError::Syntax { line: start, message: "unterminated comment".to_string() }),
+            }
+        }
+    }
+
+    fn read_symbol(&mut self) -> String {
+        self.read_while(is_symbol_char)
+    }
+
+    /// Reads characters matching `pred` from the current line.
+    fn read_while<P: Fn(char) -> bool>(&mut self, pred: P) -> String {
+        let rest = &self.line[self.pos..];
+        let end = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        let word = rest[..end].to_string();
+        self.pos += end;
+        word
+    }
+
+    /// Skips whitespace across lines and returns the next character.
+    fn skip_whitespace(&mut self) -> Result<Option<char>, PgnError> {
+        loop {
+            match self.peek()? {
+                // Byte order marks are common at the start of files
+                Some(c) if c.is_whitespace() || c == '\u{feff}' => self.bump(),
+                c => return Ok(c),
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<char>, PgnError> {
+        while self.pos >= self.line.len() {
+            if !self.next_line()? {
+                return Ok(None);
+            }
+        }
+        Ok(self.line[self.pos..].chars().next())
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.line[self.pos..].chars().next() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn next_line(&mut self) -> Result<bool, PgnError> {
+        self.buf.clear();
+        self.line.clear();
+        self.pos = 0;
+        if self.reader.read_until(b'\n', &mut self.buf)? == 0 {
+            return Ok(false);
+        }
+        self.line_number += 1;
+        self.line.push_str(&String::from_utf8_lossy(&self.buf));
+        // Lines starting with % are escaped from PGN processing
+        if self.line.starts_with('%') {
+            self.pos = self.line.len();
+        }
+        Ok(true)
+    }
+
+    /// Skips the rest of a game that failed to parse, up to the first line starting with a
+    /// tag that follows some movetext.
+    fn skip_rest_of_game(&mut self) -> Result<(), PgnError> {
+        let mut in_movetext = self.in_movetext;
+        // The game may have been ended, unfinished, by the next game's first tag
+        if in_movetext && self.line[self.pos..].trim_start().starts_with('[') {
+            return Ok(());
+        }
+        self.pos = self.line.len();
+        while self.next_line()? {
+            let trimmed = self.line.trim_start();
+            if trimmed.starts_with('[') {
+                if in_movetext {
+                    self.pos = self.line.len() - trimmed.len();
+                    return Ok(());
+                }
+            }
+            else if !trimmed.is_empty() {
+                in_movetext = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn syntax<S: Into<String>>(&self, message: S) -> PgnError {
+        PgnError::Syntax { line: self.line_number, message: message.into() }
+    }
+}
+
+/// Stops after the first I/O error, which would most likely repeat.
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let game = self.read_game();
+        self.failed = matches!(game, Err(PgnError::Io(_)));
+        game.transpose()
+    }
+}
+
+/// Characters of SAN moves, move numbers and results.
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '#' | '=' | ':' | '-' | '/')
+}
+
+/// A line of play being read: the main line, or a variation inside it.
+struct Line {
+    moves: Vec<PgnMove>,
+    board: Board,
+    /// The last move played and how to take it back, to start variations from
+    last: Option<(Move, Undo)>,
+    /// Comments waiting for the next move, at the start of the line or after a variation
+    pending: Vec<String>,
+    /// Whether a variation was closed since the last move
+    after_variation: bool,
+}
+
+impl Line {
+    fn new(board: Board) -> Self {
+        Self {
+            moves: Vec::new(),
+            board,
+            last: None,
+            pending: Vec::new(),
+            after_variation: false,
+        }
+    }
+
+    /// Ends the line. Comments still waiting for a move go after the last one.
+    fn into_moves(mut self) -> Vec<PgnMove> {
+        if let Some(last) = self.moves.last_mut() {
+            last.comments.append(&mut self.pending);
+        }
+        self.moves
+    }
+}
+
+/// The movetext of a game as it is read, with a stack of the variations being read.
+struct Movetext {
+    lines: Vec<Line>,
+}
+
+impl Movetext {
+    fn new(board: Board) -> Self {
+        Self { lines: vec![Line::new(board)] }
+    }
+
+    fn current(&mut self) -> &mut Line {
+        self.lines.last_mut().unwrap()
+    }
+
+    fn play(&mut self, san: &str) -> Result<(), SanError> {
+        let line = self.current();
+        let mv = line.board.parse_san(san)?;
+        let undo = line.board.make_move(mv);
+        line.last = Some((mv, undo));
+
+        let mut pgn_move = PgnMove::new(mv);
+        pgn_move.comments_before = mem::take(&mut line.pending);
+        line.moves.push(pgn_move);
+        line.after_variation = false;
+        Ok(())
+    }
+
+    fn comment(&mut self, text: &str) {
+        let (clock, eval, text) = parse_annotations(text);
+        let line = self.current();
+        if let Some(last) = line.moves.last_mut() {
+            last.clock = clock.or(last.clock);
+            last.eval = eval.or(last.eval);
+        }
+        if text.is_empty() {
+            return;
+        }
+        match line.moves.last_mut() {
+            Some(last) if !line.after_variation => last.comments.push(text),
+            _ => line.pending.push(text),
+        }
+    }
+
+    fn nag(&mut self, nag: u8) {
+        if let Some(last) = self.current().moves.last_mut() {
+            last.nags.push(nag);
+        }
+    }
+
+    /// Starts a variation replacing the last move of the current line.
+    fn open(&mut self) -> Result<(), &'static str> {
+        let line = self.current();
+        let (mv, undo) = line.last.ok_or("variation before any move")?;
+        let mut board = line.board.clone();
+        board.unmake_move(mv, undo);
+        self.lines.push(Line::new(board));
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), &'static str> {
+        if self.lines.len() == 1 {
+            return Err("unmatched ')'");
+        }
+        let variation = self.lines.pop().unwrap().into_moves();
+        if !variation.is_empty() {
+            let line = self.current();
+            line.moves.last_mut().unwrap().variations.push(variation);
+            line.after_variation = true;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<PgnMove>, &'static str> {
+        if self.lines.len() != 1 {
+            return Err("unterminated variation");
+        }
+        Ok(self.lines.pop().unwrap().into_moves())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+    use crate::board::outcome::GameResult;
+    use crate::pgn::{Game, PgnError};
+    use crate::pgn::reader::PgnReader;
+    use crate::search::Score;
+
+    fn read(pgn: &str) -> Vec<Result<Game, PgnError>> {
+        PgnReader::new(Cursor::new(pgn)).collect()
+    }
+
+    fn mainline(game: &Game) -> Vec<String> {
+        game.mainline().map(|m| m.to_string()).collect()
+    }
+
+    const GAMES: &str = r#"[Event "Casual game"]
+[Site "London"]
+[Date "1851.06.21"]
+[Round "?"]
+[White "Anderssen, Adolf"]
+[Black "Kieseritzky, Lionel"]
+[Result "1-0"]
+[ECO "C33"]
+
+1.e4 e5 2.f4 exf4 3.Bc4 Qh4+ 4.Kf1 b5 5.Bxb5 Nf6 6.Nf3 Qh6 7.d3 Nh5 8.Nh4 Qg5
+9.Nf5 c6 10.g4 Nf6 11.Rg1 cxb5 12.h4 Qg6 13.h5 Qg5 14.Qf3 Ng8 15.Bxf4 Qf6
+16.Nc3 Bc5 17.Nd5 Qxb2 18.Bd6 Bxg1 19.e5 Qxa1+ 20.Ke2 Na6 21.Nxg7+ Kd8
+22.Qf6+ Nxf6 23.Be7# 1-0
+
+[Event "Second"]
+[Result "*"]
+
+1. d4 d5 *
+"#;
+
+    #[test]
+    fn read_games() {
+        let games: Vec<Game> = read(GAMES).into_iter().map(Result::unwrap).collect();
+        assert_eq!(games.len(), 2);
+
+        let immortal = &games[0];
+        assert_eq!(immortal.tag("White"), Some("Anderssen, Adolf"));
+        assert_eq!(immortal.tag("ECO"), Some("C33"));
+        assert_eq!(immortal.tags.len(), 8);
+        assert_eq!(immortal.moves.len(), 45);
+        assert_eq!(immortal.moves[44].mv.to_string(), "d6e7");
+        assert_eq!(immortal.result, Some(GameResult::WhiteWins));
+
+        assert_eq!(mainline(&games[1]), ["d2d4", "d7d5"]);
+        assert_eq!(games[1].result, None);
+    }
+
+    #[test]
+    fn read_annotations() {
+        let pgn = r#"[Event "?"]
+
+{Game comment} 1. e4 $1 {[%clk 0:03:00] [%eval 0.3]} 1... e5!? 2. Nf3 (2. f4 exf4 (2... d5)
+{the gambit} 3. Nf3) (2. Bc4) Nc6?? ; rest of line
+% escaped line 1-0
+3. Nc3 {unfinished
+comment} 0-1"#;
+        let games = read(pgn);
+        assert_eq!(games.len(), 1);
+        let game = games[0].as_ref().unwrap();
+        assert_eq!(game.result, Some(GameResult::BlackWins));
+        assert_eq!(mainline(game), ["e2e4", "e7e5", "g1f3", "b8c6", "b1c3"]);
+
+        let moves = &game.moves;
+        assert_eq!(moves[0].comments_before, ["Game comment"]);
+        assert_eq!(moves[0].nags, [1]);
+        assert_eq!(moves[0].clock, Some(Duration::from_secs(180)));
+        assert_eq!(moves[0].eval, Some(Score::Centipawns(30)));
+        assert!(moves[0].comments.is_empty());
+        assert_eq!(moves[1].nags, [5]);
+        assert_eq!(moves[3].nags, [4]);
+        assert_eq!(moves[3].comments, ["rest of line"]);
+        assert_eq!(moves[4].comments, ["unfinished comment"]);
+
+        let variations = &moves[2].variations;
+        assert_eq!(variations.len(), 2);
+        assert_eq!(variations[0].len(), 3);
+        assert_eq!(variations[0][0].mv.to_string(), "f2f4");
+        assert_eq!(variations[0][1].variations[0][0].mv.to_string(), "d7d5");
+        assert!(variations[0][1].comments.is_empty());
+        assert_eq!(variations[0][2].comments_before, ["the gambit"]);
+        assert_eq!(variations[1][0].mv.to_string(), "f1c4");
+    }
+
+    #[test]
+    fn read_from_fen() {
+        let pgn = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1"]
+
+1... Kd7 2. e4 *"#;
+        let game = read(pgn).pop().unwrap().unwrap();
+        assert_eq!(mainline(&game), ["e8d7", "e2e4"]);
+    }
+
+    #[test]
+    fn read_without_result() {
+        let games = read("1. e4 e5\n\n[Event \"Next\"]\n\n1. d4");
+        assert_eq!(games.len(), 2);
+        assert_eq!(mainline(games[0].as_ref().unwrap()), ["e2e4", "e7e5"]);
+        assert_eq!(games[1].as_ref().unwrap().tag("Event"), Some("Next"));
+    }
+
+    #[test]
+    fn read_errors_recover() {
+        let pgn = r#"[Event "Broken"]
+
+1. e4 e5 2. Ke3 Nc6 1-0
+
+[Event "Bad variation"]
+
+1. e4 (1. d4 e5 *
+
+[Event "Fine"]
+
+1. e4 *
+
+[Event "Unterminated variation"]
+
+1. e4 (1. d4
+
+[Event "Next"]
+1. d4 *
+"#;
+        let games = read(pgn);
+        assert_eq!(games.len(), 5);
+        assert!(matches!(&games[0], Err(PgnError::San { line: 3, san, .. }) if san == "Ke3"));
+        assert!(matches!(&games[1], Err(PgnError::Syntax { .. })));
+        assert_eq!(games[2].as_ref().unwrap().tag("Event"), Some("Fine"));
+        assert!(matches!(&games[3], Err(PgnError::Syntax { .. })));
+        assert_eq!(games[4].as_ref().unwrap().tag("Event"), Some("Next"));
+    }
+
+    #[test]
+    fn read_tag_escapes() {
+        let game = read(r#"[Event "The \"best\" game \\ ever"] *"#).pop().unwrap().unwrap();
+        assert_eq!(game.tag("Event"), Some(r#"The "best" game \ ever"#));
+        assert!(game.moves.is_empty());
+        assert!(read("").is_empty());
+        assert!(matches!(read("[Event]").pop(), Some(Err(PgnError::Syntax { line: 1, .. }))));
+    }
+}
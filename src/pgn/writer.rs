@@ -0,0 +1,229 @@
+use std::io::Write;
+use crate::board::Board;
+use crate::piece::colour::Colour;
+use crate::pgn::{format_clock, format_eval, Game, PgnError, PgnMove, SEVEN_TAG_ROSTER};
+
+/// The line length the PGN standard recommends for export.
+pub const DEFAULT_LINE_WIDTH: usize = 80;
+
+/// Writes games in the PGN export format: the seven tag roster first, then the other
+/// tags, then movetext wrapped to the line width.
+pub struct PgnWriter<W> {
+    writer: W,
+    width: usize,
+}
+
+impl<W: Write> PgnWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_line_width(writer, DEFAULT_LINE_WIDTH)
+    }
+
+    pub fn with_line_width(writer: W, width: usize) -> Self {
+        Self { writer, width }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    pub fn write_game(&mut self, game: &Game) -> Result<(), PgnError> {
+        let board = game.start_position()?;
+        let result = game.result.map_or_else(|| "*".to_string(), |r| r.to_string());
+
+        for name in SEVEN_TAG_ROSTER.iter() {
+            let value = match *name {
+                "Result" => result.as_str(),
+                "Date" => game.tag(name).unwrap_or("????.??.??"),
+                _ => game.tag(name).unwrap_or("?"),
+            };
+            writeln!(self.writer, "[{} \"{}\"]", name, escape(value))?;
+        }
+        for (name, value) in game.tags.iter().filter(|(n, _)| !SEVEN_TAG_ROSTER.contains(&n.as_str())) {
+            writeln!(self.writer, "[{} \"{}\"]", name, escape(value))?;
+        }
+        writeln!(self.writer)?;
+
+        let mut tokens = Vec::new();
+        movetext(&board, &game.moves, &mut tokens);
+        tokens.push(result);
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > self.width {
+                writeln!(self.writer, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(self.writer, "{}", line)?;
+        writeln!(self.writer)?;
+        Ok(())
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Appends the tokens of a line of play from `board`, with its variations.
+fn movetext(board: &Board, moves: &[PgnMove], tokens: &mut Vec<String>) {
+    let mut board = board.clone();
+    // Black moves need their number at the start of a line and after an interruption
+    let mut numbered = false;
+
+    for pgn_move in moves {
+        for text in pgn_move.comments_before.iter() {
+            comment(text, tokens);
+            numbered = false;
+        }
+
+        match board.player() {
+            Colour::White => tokens.push(format!("{}.", board.full_moves())),
+            Colour::Black if !numbered => tokens.push(format!("{}...", board.full_moves())),
+            Colour::Black => {}
+        }
+        tokens.push(board.to_san(pgn_move.mv));
+        numbered = true;
+
+        for nag in pgn_move.nags.iter() {
+            tokens.push(format!("${}", nag));
+        }
+
+        let mut annotations = Vec::new();
+        if let Some(clock) = pgn_move.clock {
+            annotations.push(format!("[%clk {}]", format_clock(clock)));
+        }
+        if let Some(eval) = pgn_move.eval {
+            annotations.push(format!("[%eval {}]", format_eval(eval)));
+        }
+        if !annotations.is_empty() {
+            comment(&annotations.join(" "), tokens);
+            numbered = false;
+        }
+        for text in pgn_move.comments.iter() {
+            comment(text, tokens);
+            numbered = false;
+        }
+
+        for variation in pgn_move.variations.iter().filter(|v| !v.is_empty()) {
+            let start = tokens.len();
+            movetext(&board, variation, tokens);
+            tokens[start].insert(0, '(');
+            tokens.last_mut().unwrap().push(')');
+            numbered = false;
+        }
+
+        board.make_move(pgn_move.mv);
+    }
+}
+
+/// Appends a comment word by word, so that it can be wrapped like the rest of the
+/// movetext. Braces cannot be escaped inside a comment, so closing ones are dropped.
+fn comment(text: &str, tokens: &mut Vec<String>) {
+    let start = tokens.len();
+    tokens.extend(text.split_whitespace().map(|word| word.replace('}', "")).filter(|w| !w.is_empty()));
+    if tokens.len() == start {
+        tokens.push(String::new());
+    }
+    tokens[start].insert(0, '{');
+    tokens.last_mut().unwrap().push('}');
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+    use crate::board::outcome::GameResult;
+    use crate::pgn::Game;
+    use crate::pgn::reader::PgnReader;
+    use crate::pgn::writer::PgnWriter;
+    use crate::search::Score;
+
+    fn write(game: &Game, width: usize) -> String {
+        let mut writer = PgnWriter::with_line_width(Vec::new(), width);
+        writer.write_game(game).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    fn read(pgn: &str) -> Game {
+        PgnReader::new(Cursor::new(pgn)).read_game().unwrap().unwrap()
+    }
+
+    #[test]
+    fn write_tags() {
+        let mut game = Game::default();
+        game.set_tag("ECO", "C20");
+        game.set_tag("White", "Someone \"quoted\"");
+        game.result = Some(GameResult::Draw);
+
+        assert_eq!(write(&game, 80), r#"[Event "?"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "Someone \"quoted\""]
+[Black "?"]
+[Result "1/2-1/2"]
+[ECO "C20"]
+
+1/2-1/2
+
+"#);
+    }
+
+    #[test]
+    fn write_movetext() {
+        let mut game = read("1. e4 e5 2. Nf3 *");
+        game.moves[0].nags.push(1);
+        game.moves[0].clock = Some(Duration::from_secs(180));
+        game.moves[1].eval = Some(Score::Centipawns(-25));
+        game.moves[1].comments.push("Open} game".to_string());
+        game.moves[2].comments_before.push("Now".to_string());
+        let variation = read("1. e4 c5 2. Nf3 d6 *").moves.split_off(1);
+        game.moves[1].variations.push(variation);
+
+        let expected = "1. e4 $1 {[%clk 0:03:00]} 1... e5 {[%eval -0.25]} {Open game} (1... c5 2. Nf3
+d6) {Now} 2. Nf3 *";
+        let written = write(&game, 80);
+        assert!(written.ends_with(&format!("\n\n{}\n\n", expected)), "{}", written);
+
+        game.moves[1].comments[0] = "Open game".to_string();
+        assert_eq!(read(&written).moves, game.moves);
+    }
+
+    #[test]
+    fn write_wraps_lines() {
+        let game = read("1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 1/2-1/2");
+        let written = write(&game, 20);
+        let movetext: Vec<&str> = written.lines().skip(8).collect();
+        assert_eq!(movetext[0], "1. e4 e5 2. Nf3 Nc6");
+        assert!(movetext.iter().all(|line| line.len() <= 20));
+        assert_eq!(movetext.last().unwrap(), &"");
+    }
+
+    #[test]
+    fn write_read_round_trip() {
+        let pgn = r#"[Event "Round trip"]
+[Site "?"]
+[Date "2024.01.01"]
+[Round "1"]
+[White "A"]
+[Black "B"]
+[Result "0-1"]
+[SetUp "1"]
+[FEN "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1"]
+
+1... O-O-O {[%clk 0:01:00.5] [%eval #-3]} 2. Qxf6 $6 {A long comment that should
+be wrapped over several lines} (2. O-O {castles} 2... Kb8 (2... Rh5) 3. a3) (2.
+Ng4 hxg2) 2... Qxf6 0-1
+
+"#;
+        let game = read(pgn);
+        let written = write(&game, 80);
+        assert_eq!(written, pgn);
+        assert_eq!(read(&written), game);
+        assert_eq!(game.moves[0].clock, Some(Duration::from_millis(60_500)));
+    }
+}
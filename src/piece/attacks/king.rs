@@ -4,24 +4,28 @@ use crate::board_representation::bitboard::shift::Direction::{
 };
 use crate::board_representation::bitboard::BitBoard;
 use crate::board_representation::square::Square;
-use once_cell::sync::Lazy;
-use std::convert::TryFrom;
 
 const KING_ATTACKS: [Direction; 8] = [
     North, NorthEast, East, SouthEast, South, SouthWest, West, NorthWest,
 ];
 
-pub static ATTACK_TABLE_KING: Lazy<[BitBoard; 64]> = Lazy::new(|| {
-    let mut bbs: [BitBoard; 64] = [0_u64.into(); 64];
+pub static ATTACK_TABLE_KING: [BitBoard; 64] = generate();
+
+pub const fn generate() -> [BitBoard; 64] {
+    let mut bbs = [BitBoard::new(0); 64];
     // For each square on the board
-    for (square, bitboard) in bbs.iter_mut().enumerate() {
+    let mut square = 0;
+    while square < 64 {
         // Apply the eight directions once
-        *bitboard = KING_ATTACKS.iter().fold(0_u64.into(), |acc, step| {
-            acc | {
-                let b: BitBoard = Square::try_from(square as u64).unwrap().into();
-                b.shift(*step)
-            }
-        });
+        let b = BitBoard::from_square(Square::new(square as u64));
+        let mut attacks = 0;
+        let mut i = 0;
+        while i < KING_ATTACKS.len() {
+            attacks |= b.shift(KING_ATTACKS[i]).value();
+            i += 1;
+        }
+        bbs[square] = BitBoard::new(attacks);
+        square += 1;
     }
     bbs
-});
+}
@@ -2,8 +2,6 @@ use crate::board_representation::bitboard::shift::Direction;
 use crate::board_representation::bitboard::shift::Direction::{East, North, South, West};
 use crate::board_representation::bitboard::BitBoard;
 use crate::board_representation::square::Square;
-use once_cell::sync::Lazy;
-use std::convert::TryFrom;
 
 const KNIGHT_ATTACKS: [[Direction; 3]; 8] = [
     [North, North, East],
@@ -16,19 +14,25 @@ const KNIGHT_ATTACKS: [[Direction; 3]; 8] = [
     [West, North, North],
 ];
 
-pub static ATTACK_TABLE_KNIGHT: Lazy<[BitBoard; 64]> = Lazy::new(|| {
-    let mut bbs: [BitBoard; 64] = [0_u64.into(); 64];
+pub static ATTACK_TABLE_KNIGHT: [BitBoard; 64] = generate();
+
+pub const fn generate() -> [BitBoard; 64] {
+    let mut bbs = [BitBoard::new(0); 64];
     // For each square on the board
-    for (square, bitboard) in bbs.iter_mut().enumerate() {
+    let mut square = 0;
+    while square < 64 {
         // For each of the (up to) 8 attacked squares
-        *bitboard = KNIGHT_ATTACKS.iter().fold(0_u64.into(), |attacks, steps| {
-            attacks
-                | steps.iter().fold(
-                    // Apply the 3 shifts
-                    BitBoard::from(Square::try_from(square as u64).unwrap()),
-                    |square, step| square.shift(*step),
-                )
-        })
+        let mut attacks = 0;
+        let mut i = 0;
+        while i < KNIGHT_ATTACKS.len() {
+            // Apply the 3 shifts
+            let steps = KNIGHT_ATTACKS[i];
+            let target = BitBoard::from_square(Square::new(square as u64));
+            attacks |= target.shift(steps[0]).shift(steps[1]).shift(steps[2]).value();
+            i += 1;
+        }
+        bbs[square] = BitBoard::new(attacks);
+        square += 1;
     }
     bbs
-});
+}
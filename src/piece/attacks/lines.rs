@@ -0,0 +1,93 @@
+use crate::board_representation::bitboard::BitBoard;
+use crate::board_representation::square::Square;
+use crate::piece::piecetype::PieceType;
+use once_cell::sync::Lazy;
+use std::convert::TryFrom;
+
+/// `BETWEEN[a][b]` holds the squares strictly between `a` and `b` when they share a rank,
+/// file or diagonal, and is empty otherwise.
+pub static BETWEEN: Lazy<[[BitBoard; 64]; 64]> = Lazy::new(|| {
+    let mut bbs = [[BitBoard::new(0); 64]; 64];
+    for (a, row) in bbs.iter_mut().enumerate() {
+        let a = Square::try_from(a as u64).unwrap();
+        for (b, bitboard) in row.iter_mut().enumerate() {
+            let b = Square::try_from(b as u64).unwrap();
+            let (a_bb, b_bb) = (BitBoard::from(a), BitBoard::from(b));
+
+            // Each square's attacks, blocked by the other, overlap exactly in between
+            if !(PieceType::rook_attack(a, 0.into()) & b_bb).is_empty() {
+                *bitboard = PieceType::rook_attack(a, b_bb) & PieceType::rook_attack(b, a_bb);
+            }
+            else if !(PieceType::bishop_attack(a, 0.into()) & b_bb).is_empty() {
+                *bitboard = PieceType::bishop_attack(a, b_bb) & PieceType::bishop_attack(b, a_bb);
+            }
+        }
+    }
+    bbs
+});
+
+/// `LINE[a][b]` holds the whole rank, file or diagonal through `a` and `b`, both included,
+/// and is empty when they aren't aligned.
+pub static LINE: Lazy<[[BitBoard; 64]; 64]> = Lazy::new(|| {
+    let mut bbs = [[BitBoard::new(0); 64]; 64];
+    for (a, row) in bbs.iter_mut().enumerate() {
+        let a = Square::try_from(a as u64).unwrap();
+        for (b, bitboard) in row.iter_mut().enumerate() {
+            let b = Square::try_from(b as u64).unwrap();
+            let (a_bb, b_bb) = (BitBoard::from(a), BitBoard::from(b));
+
+            let (rook_a, rook_b) = (PieceType::rook_attack(a, 0.into()), PieceType::rook_attack(b, 0.into()));
+            let (bishop_a, bishop_b) = (PieceType::bishop_attack(a, 0.into()), PieceType::bishop_attack(b, 0.into()));
+            if !(rook_a & b_bb).is_empty() {
+                *bitboard = (rook_a & rook_b) | a_bb | b_bb;
+            }
+            else if !(bishop_a & b_bb).is_empty() {
+                *bitboard = (bishop_a & bishop_b) | a_bb | b_bb;
+            }
+        }
+    }
+    bbs
+});
+
+/// Squares strictly between two squares on a shared rank, file or diagonal; empty otherwise.
+pub fn between(a: Square, b: Square) -> BitBoard {
+    BETWEEN[a.value() as usize][b.value() as usize]
+}
+
+/// The full rank, file or diagonal through two aligned squares; empty otherwise.
+pub fn line(a: Square, b: Square) -> BitBoard {
+    LINE[a.value() as usize][b.value() as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board_representation::bitboard::BitBoard;
+    use crate::board_representation::square::Square;
+    use crate::piece::attacks::lines::{between, line};
+    use crate::testing::{square, squares};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn between_squares() {
+        assert_eq!(between(square("a1"), square("a4")), squares(&["a2", "a3"]));
+        assert_eq!(between(square("h8"), square("c3")), squares(&["g7", "f6", "e5", "d4"]));
+        assert_eq!(between(square("b2"), square("c3")), BitBoard::new(0));
+        assert_eq!(between(square("a1"), square("b3")), BitBoard::new(0));
+        assert_eq!(between(square("e4"), square("e4")), BitBoard::new(0));
+    }
+
+    #[test]
+    fn line_through_squares() {
+        assert_eq!(line(square("c3"), square("e5")), squares(&["a1", "b2", "c3", "d4", "e5", "f6", "g7", "h8"]));
+        assert_eq!(line(square("d1"), square("d2")).count(), 8);
+        assert_eq!(line(square("a1"), square("b3")), BitBoard::new(0));
+
+        for a in 0..64_u64 {
+            for b in 0..64_u64 {
+                let (a, b) = (Square::try_from(a).unwrap(), Square::try_from(b).unwrap());
+                assert_eq!(between(a, b), between(b, a));
+                assert_eq!(line(a, b) & between(a, b), between(a, b));
+            }
+        }
+    }
+}
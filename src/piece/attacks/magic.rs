@@ -1,78 +1,65 @@
 use crate::board_representation::bitboard::BitBoard;
-use once_cell::sync::Lazy;
 use crate::board_representation::square::Square;
-use std::convert::TryFrom;
-use crate::board_representation::bitboard::files_ranks::{FILE_A_BITBOARD, FILE_H_BITBOARD, RANK_8_BITBOARD, RANK_1_BITBOARD};
+use crate::piece::attacks::magic_numbers::{MAGIC_NUMBERS_BISHOP, MAGIC_NUMBERS_ROOK};
+use crate::piece::attacks::slider;
 use crate::piece::piecetype::PieceType;
+use crate::board_representation::bitboard::shift::Direction;
 use crate::board_representation::bitboard::shift::Direction::{North, East, South, West, NorthEast, SouthWest, SouthEast, NorthWest};
+use crate::common::prng::Prng;
 
-macro_rules! populate_magic {
-    ($table: expr, $magics: expr, $magic_numbers: expr, $atk_directions:expr) => {
-        {
-            let attack_directions = $atk_directions;
-            let mut prev_offset = 0;
-            let mut size = 0;
-
-            // Populate the magic fields
-            for (square, magic) in $magics.iter_mut().enumerate() {
-                let sq = Square::try_from(square as u64).unwrap();
-                let mut reference = [BitBoard::new(0); 4096];
-
-                let edges = ((RANK_1_BITBOARD | RANK_8_BITBOARD) & !BitBoard::bitboard_of_rank(sq)) |
-                    ((FILE_A_BITBOARD | FILE_H_BITBOARD) & !BitBoard::bitboard_of_file(sq));
-
-                magic.mask = PieceType::sliding_attack(attack_directions, sq, 0.into()) & !edges;
-                magic.magic = $magic_numbers[square];
-                magic.shift = 64_u64 - (u64::from(magic.mask).count_ones() as u64);
-
-                magic.table = prev_offset + size;
-                prev_offset = magic.table;
-
-                // Carry-Rippler
-                let mut b: BitBoard = 0.into();
-                size = 0;
-                loop {
-                    reference[size] = PieceType::sliding_attack(attack_directions, sq, b);
-
-                    let idx: usize = u64::from(((b & magic.mask) * (magic.magic.into())) >> (magic.shift.into())) as usize;
-                    $table[magic.table + idx] = reference[size];
-
-                    size += 1;
-                    b = (b - magic.mask) & magic.mask;
-                    if b == 0.into() {
-                        break;
-                    }
-                }
-            }
+// `ROOK_ATTACKS` and `BISHOP_ATTACKS`, generated by build.rs from the magic numbers
+include!(concat!(env!("OUT_DIR"), "/sliding_attacks.rs"));
+
+pub const ROOK_DIRECTIONS: [Direction; 4] = [North, East, South, West];
+pub const BISHOP_DIRECTIONS: [Direction; 4] = [NorthEast, SouthEast, SouthWest, NorthWest];
+
+// build.rs generates the tables from the steps in slider.rs, which cannot name `Direction`
+const _: () = assert!(same_steps(Direction::steps(ROOK_DIRECTIONS), slider::ROOK_STEPS));
+const _: () = assert!(same_steps(Direction::steps(BISHOP_DIRECTIONS), slider::BISHOP_STEPS));
+
+const fn same_steps(a: [(i8, i8); 4], b: [(i8, i8); 4]) -> bool {
+    let mut i = 0;
+    while i < a.len() {
+        if a[i].0 != b[i].0 || a[i].1 != b[i].1 {
+            return false;
         }
-    };
+        i += 1;
+    }
+    true
 }
 
-pub static SLIDING_ROOK: Lazy<SlidingRook> = Lazy::new(|| {
-    let mut table = [BitBoard::new(0); 0x19000];
-    let mut magics = [Magic::default(); 64];
+/// Entries in the rook table: the sum over all squares of 2^(relevant occupancy bits).
+pub const ROOK_TABLE_SIZE: usize = 0x19000;
+/// Entries in the bishop table, as for `ROOK_TABLE_SIZE`.
+pub const BISHOP_TABLE_SIZE: usize = 0x1480;
 
-    populate_magic!(table, magics, MAGIC_NUMBERS_ROOK, [North, East, South, West]);
+pub static SLIDING_ROOK: SlidingRook = SlidingRook::new(&ROOK_ATTACKS, populate_magic(ROOK_DIRECTIONS, &MAGIC_NUMBERS_ROOK));
 
-    SlidingRook::new(table, magics)
-});
+pub static SLIDING_BISHOP: SlidingBishop = SlidingBishop::new(&BISHOP_ATTACKS, populate_magic(BISHOP_DIRECTIONS, &MAGIC_NUMBERS_BISHOP));
 
-pub static SLIDING_BISHOP: Lazy<SlidingBishop> = Lazy::new(|| {
-    let mut table = [BitBoard::new(0); 0x1480];
-    let mut magics = [Magic::default(); 64];
+/// The magic fields of every square, laid out as build.rs lays out the tables: each square
+/// in turn takes the next `2^bits` entries.
+pub const fn populate_magic(attack_directions: [Direction; 4], magic_numbers: &[u64; 64]) -> [Magic; 64] {
+    let mut magics = [Magic::new(0, BitBoard::new(0), 0, 0); 64];
+    let mut offset = 0;
 
-    populate_magic!(table, magics, MAGIC_NUMBERS_BISHOP, [NorthEast, SouthEast, SouthWest, NorthWest]);
-
-    SlidingBishop::new(table, magics)
-});
+    let mut square = 0;
+    while square < 64 {
+        let mask = relevant_occupancy(attack_directions, Square::new(square as u64));
+        magics[square] = Magic::new(offset, mask, magic_numbers[square], 64 - mask.count() as u64);
+        offset += 1 << mask.count();
+        square += 1;
+    }
+    magics
+}
 
 pub struct SlidingRook {
-    pub table: [BitBoard; 0x19000],
+    pub table: &'static [u64; ROOK_TABLE_SIZE],
     pub magic: [Magic; 64],
 }
 
 impl SlidingRook {
-    pub fn new(table: [BitBoard; 0x19000], magic: [Magic; 64]) -> Self {
+    pub const fn new(table: &'static [u64; ROOK_TABLE_SIZE], magic: [Magic; 64]) -> Self {
         Self {
             table,
             magic
@@ -81,12 +68,12 @@ impl SlidingRook {
 }
 
 pub struct SlidingBishop {
-    pub table: [BitBoard; 0x1480],
+    pub table: &'static [u64; BISHOP_TABLE_SIZE],
     pub magic: [Magic; 64],
 }
 
 impl SlidingBishop {
-    pub fn new(table: [BitBoard; 0x1480], magic: [Magic; 64]) -> Self {
+    pub const fn new(table: &'static [u64; BISHOP_TABLE_SIZE], magic: [Magic; 64]) -> Self {
         Self {
             table,
             magic
@@ -103,7 +90,7 @@ pub struct Magic {
 }
 
 impl Magic {
-    pub fn new(table: usize, mask: BitBoard, magic: u64, shift: u64) -> Self {
+    pub const fn new(table: usize, mask: BitBoard, magic: u64, shift: u64) -> Self {
         Self {
             table,
             mask,
@@ -140,24 +127,121 @@ impl Default for Magic {
     }
 }
 
-#[allow(clippy::unreadable_literal)]
-static MAGIC_NUMBERS_BISHOP: [u64; 64] =
-    [306397059236266368, 6638343277122827280, 10377420549504106496, 9193021019258913, 2306408226914042898, 10379110636817760276, 27167319028441088, 7566153073497751552,
-        1513227076520969216, 301917653126479936, 72075465430409232, 2343002121441460228, 36033212782477344, 9223373154083475456, 6935629192638251008, 72621648200664064,
-        2310506081245267984, 2533291987569153, 146934404644733024, 1838417834950912, 579856052833622016, 1729946448243595776, 705208029025040, 2886877732040869888,
-        10092575566416331020, 5635409948247040, 738739924278198804, 4648849515743289408, 9233786889293807616, 1155253577929753088, 435164712050360592, 3026700562025580641,
-        4612284839965491969, 10448650511900137472, 571823356120080, 40569782189687936, 148620986995048708, 4901113822871308288, 4612077461748908288, 10204585674276944,
-        2534512027246592, 5766297627561820676, 13809969191200768, 1153062656578422784, 9318235838682899712, 11533824475839595776, 433770548762247233, 92326036501692936,
-        9227053213059129360, 577024872779350852, 108087561569959936, 582151826703646856, 81404176367767, 316415319130374273, 9113856212762624, 145453328103440392,
-        441392350330618400, 1126492748710916, 2309220790581891072, 3026423624667006980, 18019391702696464, 4516931289817600, 1450317422841301124, 9246488805123342592];
-
-#[allow(clippy::unreadable_literal)]
-static MAGIC_NUMBERS_ROOK: [u64; 64] =
-    [36028867955671040, 2395917338224361536, 936757656041832464, 648535942831284356, 36037595259731970, 13943151043426386048, 432349966580056576, 4683745813775001856,
-        1191624314978336800, 4611756662317916160, 4625338105090543616, 140806208356480, 1688987371057664, 9288708641522688, 153403870897537280, 281550411726850,
-        2401883155071024, 1206964838111645696, 166705754384925184, 36039792408011264, 10376580514281768960, 9148486532465664, 578787319189340418, 398007816633254020,
-        2341872150903791616, 2314850762536009728, 297238127310798880, 2251868801728768, 2594082183614301184, 820222482337235456, 37717655469424904, 577596144088011012,
-        1152991874030502016, 3171026856472219648, 20415869351890944, 4611844348286345472, 2455605323386324224, 140754676613632, 1740713828645089416, 58361257132164,
-        70370893791232, 9227880322828615684, 72092778695295040, 577023839834341392, 4723150143565660416, 563087661073408, 651083773116450, 72128789630550047,
-        153192758223054976, 869194865525653568, 4972009250306933248, 1031325449119138048, 1297041090863464576, 580401419157405824, 1657992643584, 306245066729521664,
-        15206439601351819394, 14143290885479661953, 1688988407201810, 18065251325837538, 1152927311403745429, 162411078742050817, 334255838724676, 27323018585852550];
+/// `slider::relevant_occupancy` for `Direction`s and a `Square`, as a `BitBoard`.
+pub const fn relevant_occupancy(attack_directions: [Direction; 4], square: Square) -> BitBoard {
+    BitBoard::new(slider::relevant_occupancy(Direction::steps(attack_directions), square.value()))
+}
+
+/// Every subset of `mask`, starting with the empty set. See `slider::subsets`.
+pub fn occupancy_subsets(mask: BitBoard) -> impl Iterator<Item = BitBoard> {
+    slider::subsets(mask.into()).map(BitBoard::new)
+}
+
+/// Builds the table that build.rs generates, at runtime. See `slider::attack_table`.
+pub fn attack_table(attack_directions: [Direction; 4], magic_numbers: &[u64; 64]) -> Vec<BitBoard> {
+    slider::attack_table(Direction::steps(attack_directions), magic_numbers)
+        .into_iter()
+        .map(BitBoard::new)
+        .collect()
+}
+
+/// Checks that `magic` maps every occupancy of the square's relevant squares to an index
+/// of `64 - bits` bits whose table entry is shared only by occupancies with the same attacks.
+pub fn verify_magic(attack_directions: [Direction; 4], square: Square, magic: u64) -> bool {
+    let mask = relevant_occupancy(attack_directions, square);
+    let shift = 64 - mask.count();
+    let mut table = vec![None; 1 << mask.count()];
+
+    occupancy_subsets(mask).all(|occupancy| {
+        let attacks = PieceType::sliding_attack(attack_directions, square, occupancy);
+        let idx = (u64::from(occupancy).wrapping_mul(magic) >> shift) as usize;
+        *table[idx].get_or_insert(attacks) == attacks
+    })
+}
+
+/// Searches for a magic number for `square` by trial and error. Candidates are the AND of
+/// three random numbers, since magics with few bits set are far more likely to work.
+pub fn find_magic(attack_directions: [Direction; 4], square: Square, prng: &mut Prng) -> u64 {
+    let mask = relevant_occupancy(attack_directions, square);
+    let shift = 64 - mask.count();
+    let occupancies: Vec<u64> = occupancy_subsets(mask).map(u64::from).collect();
+    let attacks: Vec<BitBoard> = occupancies.iter()
+        .map(|occ| PieceType::sliding_attack(attack_directions, square, (*occ).into()))
+        .collect();
+
+    let mut table = vec![(0_u32, BitBoard::new(0)); occupancies.len()];
+    for attempt in 1.. {
+        let magic = prng.next_u64() & prng.next_u64() & prng.next_u64();
+        // The top bits form the index, so a magic that spreads few mask bits there is hopeless
+        if (u64::from(mask).wrapping_mul(magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+
+        // Entries stamped with an older attempt count as empty, so the table is never cleared
+        let collision_free = occupancies.iter().zip(attacks.iter()).all(|(occ, atk)| {
+            let entry = &mut table[(occ.wrapping_mul(magic) >> shift) as usize];
+            if entry.0 != attempt {
+                *entry = (attempt, *atk);
+            }
+            entry.1 == *atk
+        });
+        if collision_free {
+            return magic;
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use crate::board_representation::square::Square;
+    use crate::common::prng::Prng;
+    use crate::piece::attacks::magic::{find_magic, occupancy_subsets, relevant_occupancy, verify_magic, BISHOP_DIRECTIONS, ROOK_DIRECTIONS};
+    use crate::piece::attacks::magic_numbers::{MAGIC_NUMBERS_BISHOP, MAGIC_NUMBERS_ROOK};
+    use crate::piece::piecetype::PieceType;
+
+    fn squares() -> impl Iterator<Item = Square> {
+        (0..64_u64).map(|sq| Square::try_from(sq).unwrap())
+    }
+
+    #[test]
+    fn relevant_occupancy_bits() {
+        let a1 = Square::try_from(0).unwrap();
+        let e4 = Square::try_from(28).unwrap();
+        assert_eq!(relevant_occupancy(ROOK_DIRECTIONS, a1).count(), 12);
+        assert_eq!(relevant_occupancy(ROOK_DIRECTIONS, e4).count(), 10);
+        assert_eq!(relevant_occupancy(BISHOP_DIRECTIONS, a1).count(), 6);
+        assert_eq!(relevant_occupancy(BISHOP_DIRECTIONS, e4).count(), 9);
+        assert_eq!(occupancy_subsets(relevant_occupancy(ROOK_DIRECTIONS, a1)).count(), 4096);
+    }
+
+    #[test]
+    fn magic_numbers_are_valid() {
+        for sq in squares() {
+            assert!(verify_magic(ROOK_DIRECTIONS, sq, MAGIC_NUMBERS_ROOK[sq.value() as usize]), "rook {}", sq);
+            assert!(verify_magic(BISHOP_DIRECTIONS, sq, MAGIC_NUMBERS_BISHOP[sq.value() as usize]), "bishop {}", sq);
+        }
+    }
+
+    #[test]
+    fn magic_lookup_matches_slow_slider() {
+        for sq in squares() {
+            for occupancy in occupancy_subsets(relevant_occupancy(ROOK_DIRECTIONS, sq)) {
+                assert_eq!(PieceType::rook_attack(sq, occupancy), PieceType::sliding_attack(ROOK_DIRECTIONS, sq, occupancy));
+            }
+            for occupancy in occupancy_subsets(relevant_occupancy(BISHOP_DIRECTIONS, sq)) {
+                assert_eq!(PieceType::bishop_attack(sq, occupancy), PieceType::sliding_attack(BISHOP_DIRECTIONS, sq, occupancy));
+            }
+        }
+    }
+
+    #[test]
+    fn find_magic_is_valid() {
+        let mut prng = Prng::new(1);
+        for sq in [0_u64, 27, 63].iter().map(|sq| Square::try_from(*sq).unwrap()) {
+            assert!(verify_magic(ROOK_DIRECTIONS, sq, find_magic(ROOK_DIRECTIONS, sq, &mut prng)));
+            assert!(verify_magic(BISHOP_DIRECTIONS, sq, find_magic(BISHOP_DIRECTIONS, sq, &mut prng)));
+        }
+    }
+}
@@ -11,6 +11,9 @@ use crate::piece::attacks::magic::{SLIDING_ROOK, SLIDING_BISHOP};
 pub mod knight;
 pub mod king;
 pub mod magic;
+pub mod magic_numbers;
+pub mod lines;
+mod slider;
 
 impl PieceType {
     pub fn pawn_attack(pawns: BitBoard, colour: Colour) -> BitBoard {
@@ -32,7 +35,7 @@ impl PieceType {
         let idx: usize = u64::from(
             ((occupancy & magic.mask()) * (magic.magic().into())) >> (magic.shift().into())
         ) as usize;
-        SLIDING_BISHOP.table[magic.table() + idx]
+        SLIDING_BISHOP.table[magic.table() + idx].into()
     }
 
     pub fn rook_attack(square: Square, occupancy: BitBoard) -> BitBoard {
@@ -40,7 +43,7 @@ impl PieceType {
         let idx: usize = u64::from(
             ((occupancy & magic.mask()) * (magic.magic().into())) >> (magic.shift().into())
         ) as usize;
-        SLIDING_ROOK.table[magic.table() + idx]
+        SLIDING_ROOK.table[magic.table() + idx].into()
     }
 
     pub fn queen_attack(square: Square, occupancy: BitBoard) -> BitBoard {
@@ -52,20 +55,10 @@ impl PieceType {
         ATTACK_TABLE_KING[u64::from(square) as usize]
     }
 
-    pub fn sliding_attack(attack_directions: [Direction; 4], square: Square, occupancy: BitBoard) -> BitBoard {
-        let mut attacks: BitBoard = 0.into();
-
-        for direction in attack_directions.iter() {
-            let mut current_sq = BitBoard::from(square);
-            // First: Bitboard of square is not empty
-            // Second: No piece occupies that square
-            while current_sq != BitBoard::new(0) && (occupancy & current_sq) == 0.into() {
-                current_sq = current_sq.shift(*direction);
-                attacks |= current_sq;
-
-            }
-        }
-        attacks
+    /// Walks each ray until it leaves the board or hits a piece. This is slow, so it is
+    /// only used to check the magic tables.
+    pub const fn sliding_attack(attack_directions: [Direction; 4], square: Square, occupancy: BitBoard) -> BitBoard {
+        BitBoard::new(slider::ray_attacks(Direction::steps(attack_directions), square.value(), occupancy.value()))
     }
 }
 
@@ -0,0 +1,89 @@
+//! Slider attacks on plain `u64`s. The build script includes this file to generate the
+//! magic attack tables, so it must not depend on the rest of the crate.
+
+/// The rank and file steps of the rook's and bishop's rays.
+pub const ROOK_STEPS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+pub const BISHOP_STEPS: [(i8, i8); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+/// Walks each ray, given as rank and file steps, from `square` until it leaves the board or hits a piece in `occupancy`,
+/// which is included.
+pub const fn ray_attacks(steps: [(i8, i8); 4], square: u64, occupancy: u64) -> u64 {
+    let mut attacks = 0;
+
+    let mut i = 0;
+    while i < steps.len() {
+        let (rank_step, file_step) = steps[i];
+        let mut rank = (square >> 3) as i8 + rank_step;
+        let mut file = (square & 7) as i8 + file_step;
+        // Stop at the edge of the board or after the first occupied square
+        while on_board(rank, file) {
+            let target = 1 << (rank * 8 + file);
+            attacks |= target;
+            if occupancy & target != 0 {
+                break;
+            }
+            rank += rank_step;
+            file += file_step;
+        }
+        i += 1;
+    }
+    attacks
+}
+
+/// The squares whose occupancy changes a slider's attacks: its rays, minus the last
+/// square of each, since a piece on the edge of the board blocks nothing behind it.
+pub const fn relevant_occupancy(steps: [(i8, i8); 4], square: u64) -> u64 {
+    let mut mask = 0;
+
+    let mut i = 0;
+    while i < steps.len() {
+        let (rank_step, file_step) = steps[i];
+        let mut rank = (square >> 3) as i8 + rank_step;
+        let mut file = (square & 7) as i8 + file_step;
+        while on_board(rank, file) && on_board(rank + rank_step, file + file_step) {
+            mask |= 1 << (rank * 8 + file);
+            rank += rank_step;
+            file += file_step;
+        }
+        i += 1;
+    }
+    mask
+}
+
+/// Every subset of `mask`, starting with the empty set, enumerated with the Carry-Rippler trick.
+pub fn subsets(mask: u64) -> impl Iterator<Item = u64> {
+    let mut subset = Some(0_u64);
+    std::iter::from_fn(move || {
+        let current = subset?;
+        let next = current.wrapping_sub(mask) & mask;
+        subset = if next == 0 { None } else { Some(next) };
+        Some(current)
+    })
+}
+
+/// The attack table indexed by `magics`. Each square in turn takes the next `2^bits` entries,
+/// where `bits` is the size of its relevant occupancy, and its magic indexes within them.
+///
+/// Panics if a magic sends occupancies with different attacks to the same entry.
+pub fn attack_table(steps: [(i8, i8); 4], magics: &[u64; 64]) -> Vec<u64> {
+    let mut table = Vec::new();
+
+    for (square, magic) in magics.iter().enumerate() {
+        let mask = relevant_occupancy(steps, square as u64);
+        let shift = 64 - mask.count_ones();
+        let offset = table.len();
+        table.resize(offset + (1 << mask.count_ones()), 0);
+
+        for occupancy in subsets(mask) {
+            let attacks = ray_attacks(steps, square as u64, occupancy);
+            let entry = &mut table[offset + (occupancy.wrapping_mul(*magic) >> shift) as usize];
+            assert!(*entry == 0 || *entry == attacks, "magic for square {} has a collision", square);
+            *entry = attacks;
+        }
+    }
+    table
+}
+
+const fn on_board(rank: i8, file: i8) -> bool {
+    rank >= 0 && rank < 8 && file >= 0 && file < 8
+}
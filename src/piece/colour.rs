@@ -7,6 +7,15 @@ pub enum Colour {
     Black = 1,
 }
 
+impl Colour {
+    pub fn opposite(self) -> Self {
+        match self {
+            Colour::White => Colour::Black,
+            Colour::Black => Colour::White,
+        }
+    }
+}
+
 impl From<Piece> for Colour {
     fn from(value: Piece) -> Self {
         value.colour()
@@ -1,3 +1,5 @@
+use std::fmt;
+use std::fmt::Formatter;
 use crate::piece::colour::Colour;
 use crate::piece::piecetype::PieceType;
 
@@ -5,7 +7,7 @@ pub mod colour;
 pub mod piecetype;
 pub mod attacks;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Piece {
     colour: Colour,
     piece_type: PieceType
@@ -33,3 +35,21 @@ impl From<(Colour, PieceType)> for Piece {
         Piece::new(value.0, value.1)
     }
 }
+
+/// Writes the piece as its FEN letter: upper case for white, lower case for black.
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let c = match self.piece_type {
+            PieceType::P => 'p',
+            PieceType::N => 'n',
+            PieceType::B => 'b',
+            PieceType::R => 'r',
+            PieceType::Q => 'q',
+            PieceType::K => 'k',
+        };
+        match self.colour {
+            Colour::White => write!(f, "{}", c.to_ascii_uppercase()),
+            Colour::Black => write!(f, "{}", c),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::error::Error;
+use dogfish::board::Board;
+use dogfish::book::polyglot::{polyglot_key, PolyglotBook};
+
+/// `book probe <file> <fen>`: lists the book moves for a position with their weights.
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    match args.first().map(String::as_str) {
+        Some("probe") => probe(&args[1..]),
+        _ => Err("expected: book probe <file> <fen>".into()),
+    }
+}
+
+fn probe(args: &[String]) -> Result<(), Box<dyn Error>> {
+    if args.len() < 2 {
+        return Err("expected: book probe <file> <fen>".into());
+    }
+    let board: Board = args[1..].join(" ").parse()?;
+    let mut book = PolyglotBook::open(&args[0])?;
+
+    let moves = book.probe(&board)?;
+    println!("Key: {:016x}", polyglot_key(&board));
+    if moves.is_empty() {
+        println!("No book moves");
+        return Ok(());
+    }
+
+    let total: u64 = moves.iter().map(|m| u64::from(m.weight)).sum();
+    for book_move in moves.iter() {
+        println!(
+            "{:<6} weight {:>5} ({:5.1}%)",
+            book_move.mv.to_string(),
+            book_move.weight,
+            100.0 * f64::from(book_move.weight) / total.max(1) as f64
+        );
+    }
+    Ok(())
+}
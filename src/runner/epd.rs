@@ -0,0 +1,94 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+use dogfish::board::epd::Epd;
+use dogfish::search::{SearchLimits, Searcher};
+
+const DEFAULT_MOVETIME_MS: u64 = 1000;
+
+/// `epd <file> [--movetime N]`: searches every position of a test suite for N
+/// milliseconds and checks the move found against its `bm` and `am` operations.
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let path = match args.first() {
+        Some(path) => path,
+        None => return Err("expected: epd <file> [--movetime N]".into()),
+    };
+    let movetime = match args.get(1).map(String::as_str) {
+        Some("--movetime") => match args.get(2) {
+            Some(ms) => ms.parse()?,
+            None => return Err("missing value for --movetime".into()),
+        },
+        Some(other) => return Err(format!("unknown option: {}", other).into()),
+        None => DEFAULT_MOVETIME_MS,
+    };
+    let limits = SearchLimits { time: Some(Duration::from_millis(movetime)), ..SearchLimits::default() };
+
+    let mut searcher = Searcher::new();
+    let (mut solved, mut failed, mut skipped) = (0, 0, 0);
+
+    for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let name = format!("line {}", index + 1);
+
+        let epd: Epd = match line.parse() {
+            Ok(epd) => epd,
+            Err(e) => {
+                println!("{}: skipped, {}", name, e);
+                skipped += 1;
+                continue;
+            }
+        };
+        let name = epd.id().map_or(name, str::to_string);
+        let (best, avoid) = match (epd.best_moves(), epd.avoid_moves()) {
+            (Ok(best), Ok(avoid)) if !best.is_empty() || !avoid.is_empty() => (best, avoid),
+            (Err(e), _) | (_, Err(e)) => {
+                println!("{}: skipped, {}", name, e);
+                skipped += 1;
+                continue;
+            }
+            _ => {
+                println!("{}: skipped, no bm or am operation", name);
+                skipped += 1;
+                continue;
+            }
+        };
+
+        searcher.clear();
+        let result = searcher.search(&epd.board, &limits, &AtomicBool::new(false), |_| {});
+        let mv = result.best_move;
+        let found = if mv.is_null() { "none".to_string() } else { epd.board.to_san(mv) };
+
+        if (best.is_empty() || best.contains(&mv)) && !avoid.contains(&mv) {
+            solved += 1;
+            println!("{}: solved with {}", name, found);
+        }
+        else {
+            failed += 1;
+            let mut expected = Vec::new();
+            if let Some(bm) = epd.operation("bm") {
+                expected.push(format!("bm {}", bm.join(" ")));
+            }
+            if let Some(am) = epd.operation("am") {
+                expected.push(format!("am {}", am.join(" ")));
+            }
+            println!("{}: failed with {}, expected {}", name, found, expected.join("; "));
+        }
+    }
+
+    let total = solved + failed;
+    println!();
+    println!(
+        "Solved {}/{} ({:.1}%), failed {}, skipped {}",
+        solved,
+        total,
+        100.0 * solved as f64 / total.max(1) as f64,
+        failed,
+        skipped
+    );
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use dogfish::board_representation::bitboard::shift::Direction;
+use dogfish::board_representation::square::Square;
+use dogfish::common::prng::Prng;
+use dogfish::piece::attacks::magic::{find_magic, verify_magic, BISHOP_DIRECTIONS, ROOK_DIRECTIONS};
+
+/// `magics [seed]`: finds magic numbers for every square and prints them as the
+/// `MAGIC_NUMBERS_ROOK` and `MAGIC_NUMBERS_BISHOP` tables of `magic_numbers.rs`. The
+/// seed defaults to the time.
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let seed: u64 = match args.first() {
+        Some(s) => s.parse()?,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+    };
+    let mut prng = Prng::new(seed);
+
+    println!("// seed {}", seed);
+    for (name, directions) in [("MAGIC_NUMBERS_BISHOP", BISHOP_DIRECTIONS), ("MAGIC_NUMBERS_ROOK", ROOK_DIRECTIONS)].iter() {
+        let start = Instant::now();
+        let magics = find_magics(*directions, &mut prng)?;
+        eprintln!("{}: {:.3}s", name, start.elapsed().as_secs_f64());
+
+        println!("#[allow(clippy::unreadable_literal)]");
+        println!("pub const {}: [u64; 64] =", name);
+        for (i, row) in magics.chunks(8).enumerate() {
+            let row: Vec<String> = row.iter().map(u64::to_string).collect();
+            let (open, close) = match i {
+                0 => ("    [", ","),
+                7 => ("        ", "];"),
+                _ => ("        ", ","),
+            };
+            println!("{}{}{}", open, row.join(", "), close);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn find_magics(directions: [Direction; 4], prng: &mut Prng) -> Result<Vec<u64>, Box<dyn Error>> {
+    let mut magics = Vec::with_capacity(64);
+    for sq in 0..64_u64 {
+        let square = Square::try_from(sq)?;
+        let magic = find_magic(directions, square, prng);
+        if !verify_magic(directions, square, magic) {
+            return Err(format!("magic {} failed verification for {}", magic, square).into());
+        }
+        magics.push(magic);
+    }
+    Ok(magics)
+}
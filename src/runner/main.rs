@@ -1,3 +1,36 @@
+use std::env;
+use std::process;
+
+mod book;
+mod epd;
+mod magics;
+mod perft;
+mod uci;
+
+const USAGE: &str = "usage:
+    dogfish-runner                  speak UCI on stdin/stdout
+    dogfish-runner perft <depth> [fen]
+    dogfish-runner book probe <file> <fen>
+    dogfish-runner epd <file> [--movetime <ms>]
+    dogfish-runner magics [seed]";
+
 fn main() {
-    println!("Hello, world!");
-}
\ No newline at end of file
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("perft") => perft::run(&args[1..]),
+        Some("book") => book::run(&args[1..]),
+        Some("epd") => epd::run(&args[1..]),
+        Some("magics") => magics::run(&args[1..]),
+        None => uci::run(),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,39 @@
+use std::error::Error;
+use std::time::Instant;
+use dogfish::board::Board;
+use dogfish::board::fen::STARTING_FEN;
+use dogfish::perft::perft_divide;
+
+/// `perft <depth> [fen]`: prints the node count below each root move, then the total.
+/// The FEN may be passed as one quoted argument or as its six separate fields.
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let depth: u32 = match args.first() {
+        Some(d) => d.parse()?,
+        None => return Err("missing depth".into()),
+    };
+    let fen = if args.len() > 1 {
+        args[1..].join(" ")
+    }
+    else {
+        STARTING_FEN.to_string()
+    };
+    let mut board: Board = fen.parse()?;
+
+    let start = Instant::now();
+    let divide = perft_divide(&mut board, depth);
+    let elapsed = start.elapsed();
+
+    for (mv, nodes) in divide.iter() {
+        println!("{}: {}", mv, nodes);
+    }
+    let total: u64 = divide.iter().map(|(_, nodes)| nodes).sum();
+
+    println!();
+    println!("Nodes: {}", total);
+    println!(
+        "Time: {:.3}s ({:.0} nps)",
+        elapsed.as_secs_f64(),
+        total as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+    Ok(())
+}
@@ -0,0 +1,401 @@
+use std::error::Error;
+use std::io;
+use std::io::BufRead;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use dogfish::board::Board;
+use dogfish::board::castling::CastlingRights;
+use dogfish::board::fen::STARTING_FEN;
+use dogfish::board::moves::{Move, MoveList};
+use dogfish::board_representation::square::Square;
+use dogfish::eval::params::EvalParams;
+use dogfish::piece::colour::Colour;
+use dogfish::search::{Score, SearchLimits, SearchResult, Searcher};
+use dogfish::search::tt::{DEFAULT_HASH_MIB, MAX_HASH_MIB};
+
+const ENGINE_NAME: &str = "Dogfish";
+const ENGINE_AUTHOR: &str = "Anson";
+
+/// Time kept back from every move for communication with the GUI.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+/// How many moves the remaining clock is spread over when the GUI doesn't say.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// Arguments of the `go` command.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GoParams {
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub infinite: bool,
+}
+
+impl GoParams {
+    pub fn parse(tokens: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let mut params = GoParams::default();
+        let mut iter = tokens.iter();
+
+        while let Some(token) = iter.next() {
+            let mut value = || iter.next().ok_or_else(|| format!("missing value for {}", token));
+            match *token {
+                "wtime" => params.wtime = Some(parse_millis(value()?)?),
+                "btime" => params.btime = Some(parse_millis(value()?)?),
+                "winc" => params.winc = Some(parse_millis(value()?)?),
+                "binc" => params.binc = Some(parse_millis(value()?)?),
+                "movestogo" => params.movestogo = Some(value()?.parse()?),
+                "depth" => params.depth = Some(value()?.parse()?),
+                "nodes" => params.nodes = Some(value()?.parse()?),
+                "movetime" => params.movetime = Some(parse_millis(value()?)?),
+                "infinite" => params.infinite = true,
+                _ => {}
+            }
+        }
+        Ok(params)
+    }
+
+    /// How long `player` should think for, or `None` when the search is not timed.
+    pub fn time_budget(&self, player: Colour) -> Option<Duration> {
+        if let Some(movetime) = self.movetime {
+            return Some(movetime.checked_sub(MOVE_OVERHEAD).unwrap_or_default());
+        }
+
+        let (time, inc) = match player {
+            Colour::White => (self.wtime?, self.winc.unwrap_or_default()),
+            Colour::Black => (self.btime?, self.binc.unwrap_or_default()),
+        };
+        let moves_to_go = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let budget = (time / moves_to_go + inc).min(time);
+        Some(budget.checked_sub(MOVE_OVERHEAD).unwrap_or_default())
+    }
+
+    pub fn limits(&self, player: Colour) -> SearchLimits {
+        SearchLimits {
+            depth: self.depth,
+            nodes: self.nodes,
+            time: if self.infinite { None } else { self.time_budget(player) },
+        }
+    }
+}
+
+fn parse_millis(value: &str) -> Result<Duration, Box<dyn Error>> {
+    // Clocks can go negative in some GUIs when the engine overstepped
+    let millis: i64 = value.parse()?;
+    Ok(Duration::from_millis(millis.max(0) as u64))
+}
+
+/// A search running on its own thread, so that `stop` and `isready` stay responsive.
+struct SearchHandle {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+pub struct Uci {
+    board: Board,
+    searcher: Arc<Mutex<Searcher>>,
+    search: Option<SearchHandle>,
+    /// Whether castling moves are written as the king capturing its own rook
+    chess960: bool,
+}
+
+impl Uci {
+    pub fn new() -> Self {
+        Self {
+            board: STARTING_FEN.parse().unwrap(),
+            searcher: Arc::new(Mutex::new(Searcher::new())),
+            search: None,
+            chess960: false,
+        }
+    }
+
+    /// Handles one line of input. Returns `false` once the engine should exit.
+    pub fn handle(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match tokens.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return true,
+        };
+
+        let result = match command {
+            "uci" => {
+                println!("id name {}", ENGINE_NAME);
+                println!("id author {}", ENGINE_AUTHOR);
+                println!("option name Hash type spin default {} min 1 max {}", DEFAULT_HASH_MIB, MAX_HASH_MIB);
+                println!("option name EvalFile type string default <empty>");
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+                Ok(())
+            }
+            "isready" => {
+                println!("readyok");
+                Ok(())
+            }
+            "ucinewgame" => {
+                self.stop_search();
+                self.board = STARTING_FEN.parse().unwrap();
+                self.searcher.lock().unwrap().clear();
+                Ok(())
+            }
+            "position" => self.position(args),
+            "go" => self.go(args),
+            "stop" => {
+                self.stop_search();
+                Ok(())
+            }
+            "setoption" => self.set_option(args),
+            "quit" => {
+                self.stop_search();
+                return false;
+            }
+            _ => Err(format!("unknown command: {}", command).into()),
+        };
+
+        if let Err(e) = result {
+            println!("info string error: {}", e);
+        }
+        true
+    }
+
+    /// `position [startpos | fen <fen>] [moves <move>...]`
+    fn position(&mut self, args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let moves_at = args.iter().position(|t| *t == "moves").unwrap_or(args.len());
+        let mut board: Board = match args.first() {
+            Some(&"startpos") => STARTING_FEN.parse()?,
+            Some(&"fen") => args[1..moves_at].join(" ").parse()?,
+            _ => return Err("expected startpos or fen".into()),
+        };
+
+        for name in args.iter().skip(moves_at + 1) {
+            let mv = find_move(&board, name, self.chess960).ok_or_else(|| format!("illegal move: {}", name))?;
+            board.make_move(mv);
+        }
+
+        self.board = board;
+        Ok(())
+    }
+
+    fn go(&mut self, args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let params = GoParams::parse(args)?;
+        self.stop_search();
+
+        let board = self.board.clone();
+        let searcher = Arc::clone(&self.searcher);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let chess960 = self.chess960;
+        let thread = thread::spawn(move || {
+            think(&mut searcher.lock().unwrap(), board, params, thread_stop, chess960)
+        });
+
+        self.search = Some(SearchHandle { stop, thread });
+        Ok(())
+    }
+
+    /// `setoption name <id> [value <x>]`
+    fn set_option(&mut self, args: &[&str]) -> Result<(), Box<dyn Error>> {
+        let value_at = args.iter().position(|t| *t == "value").unwrap_or(args.len());
+        let name = args.get(1..value_at).map(|n| n.join(" ")).unwrap_or_default();
+        let value = args.get(value_at + 1..).map(|v| v.join(" ")).unwrap_or_default();
+
+        match name.to_lowercase().as_str() {
+            "hash" => {
+                let mib: usize = value.parse()?;
+                self.stop_search();
+                self.searcher.lock().unwrap().set_hash_size(mib);
+                Ok(())
+            }
+            "evalfile" => {
+                // An empty path goes back to the built-in parameters
+                let params = match value.as_str() {
+                    "" | "<empty>" => EvalParams::default(),
+                    path => EvalParams::load(path)?,
+                };
+                self.stop_search();
+                self.searcher.lock().unwrap().set_eval_params(params);
+                Ok(())
+            }
+            "uci_chess960" => {
+                self.chess960 = value.parse()?;
+                Ok(())
+            }
+            _ => Err(format!("unknown option: {}", name).into()),
+        }
+    }
+
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            let _ = search.thread.join();
+        }
+    }
+}
+
+impl Default for Uci {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finds the legal move written in long algebraic notation, recovering its flags. In
+/// Chess960 mode castling is written as the king capturing its own rook.
+fn find_move(board: &Board, name: &str, chess960: bool) -> Option<Move> {
+    let parsed: Move = name.parse().ok()?;
+    let mut moves = MoveList::new();
+    board.generate_legal(&mut moves);
+
+    moves.iter()
+        .find(|m| {
+            let to = if chess960 && m.is_castle() { castling_rook(board, **m) } else { Some(m.to()) };
+            m.from() == parsed.from() && to == Some(parsed.to()) && m.promotion() == parsed.promotion()
+        })
+        .copied()
+}
+
+/// The rook a castling move castles with.
+fn castling_rook(board: &Board, mv: Move) -> Option<Square> {
+    let side = if mv.is_castle_kingside() { CastlingRights::KingSide } else { CastlingRights::QueenSide };
+    board.castling_rook(board.player(), side)
+}
+
+/// Writes a move in long algebraic notation. In Chess960 mode castling is written as the
+/// king capturing its own rook (`e1h1`), since the king may not move at all.
+fn move_name(board: &Board, mv: Move, chess960: bool) -> String {
+    if chess960 && mv.is_castle() {
+        if let Some(rook) = castling_rook(board, mv) {
+            return format!("{}{}", mv.from(), rook);
+        }
+    }
+    mv.to_string()
+}
+
+/// Searches `board` and answers with `bestmove`. `infinite` searches still wait for `stop`
+/// before answering, as the protocol requires.
+fn think(searcher: &mut Searcher, board: Board, params: GoParams, stop: Arc<AtomicBool>, chess960: bool) {
+    let limits = params.limits(board.player());
+    let result = searcher.search(&board, &limits, &stop, |result| print_info(&board, result, chess960));
+
+    if params.infinite {
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+    println!("bestmove {}", move_name(&board, result.best_move, chess960));
+}
+
+fn print_info(board: &Board, result: &SearchResult, chess960: bool) {
+    let score = match result.score {
+        Score::Centipawns(cp) => format!("cp {}", cp),
+        Score::Mate(moves) => format!("mate {}", moves),
+    };
+    let millis = result.elapsed.as_millis();
+    let nps = result.nodes as u128 * 1000 / millis.max(1);
+    let mut board = board.clone();
+    let pv: Vec<String> = result.pv.iter()
+        .map(|m| {
+            let name = move_name(&board, *m, chess960);
+            board.make_move(*m);
+            name
+        })
+        .collect();
+    println!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        result.depth, score, result.nodes, nps, millis, pv.join(" ")
+    );
+}
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let mut uci = Uci::new();
+    for line in io::stdin().lock().lines() {
+        if !uci.handle(line?.trim()) {
+            break;
+        }
+    }
+    uci.stop_search();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use dogfish::piece::colour::Colour;
+    use crate::uci::{find_move, move_name, GoParams, Uci};
+
+    #[test]
+    fn go_parse() {
+        let params = GoParams::parse(&["wtime", "60000", "btime", "-20", "winc", "1000", "movestogo", "20", "depth", "7"]).unwrap();
+        assert_eq!(params.wtime, Some(Duration::from_secs(60)));
+        assert_eq!(params.btime, Some(Duration::from_secs(0)));
+        assert_eq!(params.winc, Some(Duration::from_secs(1)));
+        assert_eq!(params.movestogo, Some(20));
+        assert_eq!(params.depth, Some(7));
+        assert!(!params.infinite);
+
+        assert!(GoParams::parse(&["infinite"]).unwrap().infinite);
+        assert!(GoParams::parse(&["nodes"]).is_err());
+    }
+
+    #[test]
+    fn go_time_budget() {
+        let params = GoParams::parse(&["wtime", "60000", "btime", "30000", "winc", "1000", "binc", "1000", "movestogo", "20"]).unwrap();
+        assert_eq!(params.time_budget(Colour::White), Some(Duration::from_millis(3970)));
+        assert_eq!(params.time_budget(Colour::Black), Some(Duration::from_millis(2470)));
+
+        let params = GoParams::parse(&["movetime", "1000"]).unwrap();
+        assert_eq!(params.time_budget(Colour::Black), Some(Duration::from_millis(970)));
+
+        let params = GoParams::parse(&["depth", "5"]).unwrap();
+        assert_eq!(params.time_budget(Colour::White), None);
+    }
+
+    #[test]
+    fn position_with_moves() {
+        let mut uci = Uci::new();
+        uci.position(&["startpos", "moves", "e2e4", "e7e5", "g1f3"]).unwrap();
+        assert_eq!(uci.board.to_string(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+        uci.position(&["fen", "4k3/P7/8/8/8/8/8/4K3", "w", "-", "-", "0", "1", "moves", "a7a8q"]).unwrap();
+        assert_eq!(uci.board.to_string(), "Q3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        // Some GUIs write the promotion piece in upper case
+        uci.position(&["fen", "4k3/P7/8/8/8/8/8/4K3", "w", "-", "-", "0", "1", "moves", "a7a8N"]).unwrap();
+        assert_eq!(uci.board.to_string(), "N3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+
+        assert!(uci.position(&["startpos", "moves", "e2e5"]).is_err());
+    }
+
+    #[test]
+    fn position_chess960_castling() {
+        let fen = ["fen", "rk5r/8/8/8/8/8/8/R4KR1", "w", "GAha", "-", "0", "1", "moves"];
+        let mut uci = Uci::new();
+        assert!(uci.position(&[&fen[..], &["f1a1"]].concat()).is_err());
+
+        uci.set_option(&["name", "UCI_Chess960", "value", "true"]).unwrap();
+        uci.position(&[&fen[..], &["f1g1", "b8a8"]].concat()).unwrap();
+        assert_eq!(uci.board.to_string(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
+        uci.position(&[&fen[..], &["f1a1"]].concat()).unwrap();
+        assert_eq!(uci.board.to_string(), "rk5r/8/8/8/8/8/8/2KR2R1 b kq - 1 1");
+        assert!(uci.position(&[&fen[..], &["f1c1"]].concat()).is_err());
+
+        let board = "4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1".parse().unwrap();
+        let castle = find_move(&board, "e1a1", true).unwrap();
+        assert!(castle.is_castle_queenside());
+        assert_eq!(move_name(&board, castle, false), "e1c1");
+        assert_eq!(find_move(&board, "e1c1", false), Some(castle));
+    }
+
+    #[test]
+    fn set_hash_option() {
+        let mut uci = Uci::new();
+        assert!(uci.set_option(&["name", "Hash", "value", "4"]).is_ok());
+        assert!(uci.set_option(&["name", "Hash", "value", "lots"]).is_err());
+        assert!(uci.set_option(&["name", "Ponder", "value", "true"]).is_err());
+        assert!(uci.set_option(&["name", "EvalFile", "value", "/nonexistent/eval.txt"]).is_err());
+        assert!(uci.set_option(&["name", "EvalFile", "value", "<empty>"]).is_ok());
+    }
+}
@@ -0,0 +1,619 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use crate::board::Board;
+use crate::board::moves::{Move, MoveList, MAX_MOVES};
+use crate::board::see::PIECE_VALUES;
+use crate::board_representation::bitboard::files_ranks::{RANK_2_BITBOARD, RANK_7_BITBOARD};
+use crate::eval::Evaluator;
+use crate::eval::params::EvalParams;
+use crate::piece::colour::Colour;
+use crate::piece::piecetype::PieceType;
+use crate::search::tt::{Bound, TranspositionTable};
+
+pub mod tt;
+
+pub const MAX_PLY: usize = 128;
+
+/// Larger than any score a search can return.
+pub const INFINITY: i32 = 32_000;
+/// The score for delivering mate right now; mate in `n` plies scores `MATE - n`.
+pub const MATE: i32 = 31_000;
+pub(crate) const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+
+/// The stop flag and clock are only polled every this many nodes.
+const CHECK_INTERVAL: u64 = 2048;
+
+/// Slack given to delta pruning for positional gains the material count doesn't see.
+const DELTA_MARGIN: i32 = 200;
+
+/// When to stop searching. With every field `None` the search runs until it is stopped
+/// through the stop flag (or reaches `MAX_PLY`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+/// A search score, from the point of view of the side to move.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Score {
+    Centipawns(i32),
+    /// Mate in this many moves; negative when the side to move is getting mated.
+    Mate(i32),
+}
+
+impl Score {
+    pub fn from_internal(score: i32) -> Self {
+        if score >= MATE_BOUND {
+            Score::Mate((MATE - score + 1) / 2)
+        }
+        else if score <= -MATE_BOUND {
+            Score::Mate(-(MATE + score) / 2)
+        }
+        else {
+            Score::Centipawns(score)
+        }
+    }
+}
+
+/// The outcome of one iteration of the search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    pub best_move: Move,
+    pub score: Score,
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub pv: Vec<Move>,
+}
+
+/// Runs iterative deepening searches. One `Searcher` is meant to be reused for every
+/// search of a game, so that its transposition table carries over between moves.
+pub struct Searcher {
+    tt: TranspositionTable,
+    evaluator: Evaluator,
+}
+
+impl Searcher {
+    pub fn new() -> Self {
+        Self {
+            tt: TranspositionTable::default(),
+            evaluator: Evaluator::default(),
+        }
+    }
+
+    /// Evaluates positions with `params` from the next search on. Scores stored under the
+    /// old parameters are dropped.
+    pub fn set_eval_params(&mut self, params: EvalParams) {
+        self.evaluator = Evaluator::new(params);
+        self.tt.clear();
+    }
+
+    /// Reallocates the transposition table with about `mib` mebibytes.
+    pub fn set_hash_size(&mut self, mib: usize) {
+        self.tt.resize(mib);
+    }
+
+    /// Forgets everything learnt in earlier searches, e.g. for a new game.
+    pub fn clear(&mut self) {
+        self.tt.clear();
+    }
+
+    /// Searches `board` with iterative deepening until a limit is hit or `stop` is set,
+    /// calling `report` after every completed iteration. Returns the result of the last
+    /// completed iteration; at least depth 1 is always completed if there is a legal move.
+    pub fn search<F: FnMut(&SearchResult)>(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        stop: &AtomicBool,
+        mut report: F,
+    ) -> SearchResult {
+        self.tt.new_search();
+        let mut search = Search {
+            board: board.clone(),
+            tt: &self.tt,
+            evaluator: &mut self.evaluator,
+            limits,
+            stop,
+            start: Instant::now(),
+            nodes: 0,
+            abortable: false,
+            aborted: false,
+            pv: PvTable::new(),
+            previous_pv: Vec::new(),
+        };
+
+        let mut result = SearchResult {
+            best_move: Move::NULL,
+            score: Score::Centipawns(0),
+            depth: 0,
+            nodes: 0,
+            elapsed: Duration::default(),
+            pv: Vec::new(),
+        };
+
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32).min(MAX_PLY as u32 - 1).max(1);
+        for depth in 1..=max_depth {
+            // Depth 1 always runs to completion so that there is a move to play
+            search.abortable = depth > 1;
+            if search.abortable {
+                search.check_limits();
+            }
+
+            let score = search.negamax(depth, 0, -INFINITY, INFINITY);
+            if search.aborted {
+                break;
+            }
+
+            let pv = search.pv.line();
+            result = SearchResult {
+                best_move: pv.first().copied().unwrap_or(Move::NULL),
+                score: Score::from_internal(score),
+                depth,
+                nodes: search.nodes,
+                elapsed: search.start.elapsed(),
+                pv: pv.clone(),
+            };
+            report(&result);
+            search.previous_pv = pv;
+
+            if result.best_move.is_null() || search.out_of_time_for_next_iteration() {
+                break;
+            }
+        }
+
+        result.nodes = search.nodes;
+        result.elapsed = search.start.elapsed();
+        result
+    }
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Triangular principal variation table: row `ply` holds the best line found from `ply`.
+struct PvTable {
+    moves: [[Move; MAX_PLY]; MAX_PLY],
+    length: [usize; MAX_PLY],
+}
+
+impl PvTable {
+    fn new() -> Self {
+        Self {
+            moves: [[Move::NULL; MAX_PLY]; MAX_PLY],
+            length: [0; MAX_PLY],
+        }
+    }
+
+    fn clear(&mut self, ply: usize) {
+        self.length[ply] = ply;
+    }
+
+    /// Makes `mv` followed by the line below it the best line at `ply`.
+    fn update(&mut self, ply: usize, mv: Move) {
+        self.moves[ply][ply] = mv;
+        let child_length = if ply + 1 < MAX_PLY { self.length[ply + 1] } else { ply + 1 };
+        for next in (ply + 1)..child_length {
+            self.moves[ply][next] = self.moves[ply + 1][next];
+        }
+        self.length[ply] = child_length.max(ply + 1);
+    }
+
+    fn line(&self) -> Vec<Move> {
+        self.moves[0][..self.length[0]].to_vec()
+    }
+}
+
+/// State of a single search.
+struct Search<'a> {
+    board: Board,
+    tt: &'a TranspositionTable,
+    evaluator: &'a mut Evaluator,
+    limits: &'a SearchLimits,
+    stop: &'a AtomicBool,
+    start: Instant,
+    nodes: u64,
+    abortable: bool,
+    aborted: bool,
+    pv: PvTable,
+    previous_pv: Vec<Move>,
+}
+
+impl<'a> Search<'a> {
+    fn negamax(&mut self, depth: u32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        // A single repetition is scored as a draw: if it was worth repeating once, it is
+        // worth repeating again
+        if ply > 0 && (self.board.is_repetition(2) || self.board.is_fifty_move_draw() || self.board.has_insufficient_material()) {
+            self.pv.clear(ply);
+            return 0;
+        }
+
+        if depth == 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(ply, alpha, beta);
+        }
+
+        self.pv.clear(ply);
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.check_limits();
+        }
+        if self.aborted {
+            return 0;
+        }
+
+        let key = self.board.hash();
+        let entry = self.tt.probe(key, ply);
+        if let Some(entry) = entry {
+            // The root always searches, so that there is a principal variation to report
+            if ply > 0 && entry.depth >= depth {
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.score >= beta,
+                    Bound::Upper => entry.score <= alpha,
+                };
+                if cutoff {
+                    return entry.score;
+                }
+            }
+        }
+
+        let mut moves = MoveList::new();
+        self.board.generate_legal(&mut moves);
+        if moves.is_empty() {
+            return if self.board.in_check() { -MATE + ply as i32 } else { 0 };
+        }
+
+        let tt_move = entry.map_or(Move::NULL, |e| e.best_move);
+        let mut scores = self.order_moves(&moves, ply, tt_move);
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = Move::NULL;
+
+        for idx in 0..moves.len() {
+            let mv = pick_next(&mut moves, &mut scores, idx);
+
+            let undo = self.board.make_move(mv);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            self.board.unmake_move(mv, undo);
+
+            if self.aborted {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = mv;
+                    self.pv.update(ply, mv);
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let bound = if best >= beta {
+            Bound::Lower
+        }
+        else if best > original_alpha {
+            Bound::Exact
+        }
+        else {
+            Bound::Upper
+        };
+        self.tt.store(key, best_move, depth, bound, best, ply);
+        best
+    }
+
+    /// Searches captures and promotions only, until the position is quiet, so that leaf
+    /// scores aren't taken in the middle of an exchange. The side to move may "stand pat"
+    /// on the static evaluation instead of capturing, unless it is in check, in which case
+    /// every evasion is searched.
+    fn quiescence(&mut self, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv.clear(ply);
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.check_limits();
+        }
+        if self.aborted {
+            return 0;
+        }
+        // Captures only ever reduce material, so this is the one draw worth checking here
+        if self.board.has_insufficient_material() {
+            return 0;
+        }
+
+        let in_check = self.board.in_check();
+        let mut moves = MoveList::new();
+        if in_check {
+            self.board.generate_legal(&mut moves);
+            if moves.is_empty() {
+                return -MATE + ply as i32;
+            }
+        }
+        else {
+            self.board.generate_captures(&mut moves);
+        }
+
+        let stand_pat = self.evaluate();
+        if ply >= MAX_PLY - 1 {
+            return stand_pat;
+        }
+
+        let mut best = -INFINITY;
+        if !in_check {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            // Not even winning a queen, and promoting a pawn if one is about to, would bring
+            // the score back up to alpha
+            if stand_pat + self.max_material_gain() + DELTA_MARGIN < alpha {
+                return stand_pat;
+            }
+            best = stand_pat;
+            alpha = alpha.max(stand_pat);
+        }
+
+        let mut scores = self.order_moves(&moves, ply, Move::NULL);
+        for idx in 0..moves.len() {
+            let mv = pick_next(&mut moves, &mut scores, idx);
+
+            if !in_check {
+                // Delta pruning: skip captures that can't raise the score to alpha
+                if stand_pat + self.material_gain(mv) + DELTA_MARGIN <= alpha {
+                    continue;
+                }
+                // Captures that lose material in the exchange won't raise it either
+                if mv.is_capture() && !self.board.see_ge(mv, 0) {
+                    continue;
+                }
+            }
+
+            let undo = self.board.make_move(mv);
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.board.unmake_move(mv, undo);
+
+            if self.aborted {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    if alpha >= beta {
+                        break;
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// The most material any one move can win: a queen, plus a promotion if the side to
+    /// move has a pawn on its seventh rank.
+    fn max_material_gain(&self) -> i32 {
+        let us = self.board.player();
+        let seventh = match us {
+            Colour::White => RANK_7_BITBOARD,
+            Colour::Black => RANK_2_BITBOARD,
+        };
+        let queen = PIECE_VALUES[PieceType::Q as usize];
+        if (self.board.bb_pieces_of(us, PieceType::P) & seventh).is_empty() {
+            queen
+        }
+        else {
+            queen + queen - PIECE_VALUES[PieceType::P as usize]
+        }
+    }
+
+    /// The material `mv` wins if it isn't recaptured.
+    fn material_gain(&self, mv: Move) -> i32 {
+        let captured = if mv.is_en_passant() {
+            PIECE_VALUES[PieceType::P as usize]
+        }
+        else {
+            self.board.piece_on(mv.to()).map_or(0, |p| PIECE_VALUES[p.piece_type() as usize])
+        };
+        let promoted = mv.promotion().map_or(0, |pt| PIECE_VALUES[pt as usize] - PIECE_VALUES[PieceType::P as usize]);
+        captured + promoted
+    }
+
+    fn evaluate(&mut self) -> i32 {
+        self.evaluator.evaluate(&self.board)
+    }
+
+    /// Scores moves for ordering: the transposition table move first, then the move from
+    /// the previous iteration's principal variation, then captures that don't lose
+    /// material by most valuable victim and least valuable attacker, then promotions,
+    /// then quiet moves, then losing captures.
+    fn order_moves(&self, moves: &MoveList, ply: usize, tt_move: Move) -> [i32; MAX_MOVES] {
+        let mut scores = [0; MAX_MOVES];
+        let pv_move = self.previous_pv.get(ply).copied();
+
+        for (score, mv) in scores.iter_mut().zip(moves.iter()) {
+            *score = if *mv == tt_move {
+                2_000_000
+            }
+            else if Some(*mv) == pv_move {
+                1_000_000
+            }
+            else if mv.is_capture() {
+                let victim = match self.board.piece_on(mv.to()) {
+                    Some(p) => PIECE_VALUES[p.piece_type() as usize],
+                    None => PIECE_VALUES[PieceType::P as usize],
+                };
+                let attacker = PIECE_VALUES[self.board.piece_on(mv.from()).unwrap().piece_type() as usize];
+                let mvv_lva = 10 * victim - attacker / 10;
+                if self.board.see_ge(*mv, 0) { 100_000 + mvv_lva } else { -100_000 + mvv_lva }
+            }
+            else if let Some(piece_type) = mv.promotion() {
+                50_000 + PIECE_VALUES[piece_type as usize]
+            }
+            else {
+                0
+            };
+        }
+        scores
+    }
+
+    fn check_limits(&mut self) {
+        if !self.abortable {
+            return;
+        }
+        if self.stop.load(Ordering::Relaxed) {
+            self.aborted = true;
+        }
+        if let Some(nodes) = self.limits.nodes {
+            if self.nodes >= nodes {
+                self.aborted = true;
+            }
+        }
+        if let Some(time) = self.limits.time {
+            if self.start.elapsed() >= time {
+                self.aborted = true;
+            }
+        }
+    }
+
+    /// The next iteration usually takes several times as long as the last, so don't
+    /// start one that is unlikely to finish.
+    fn out_of_time_for_next_iteration(&self) -> bool {
+        match self.limits.time {
+            Some(time) => self.start.elapsed() >= time / 2,
+            None => false,
+        }
+    }
+}
+
+/// Selection sort step: swaps the best scoring remaining move into `idx` and returns it.
+fn pick_next(moves: &mut MoveList, scores: &mut [i32; MAX_MOVES], idx: usize) -> Move {
+    let mut best = idx;
+    for next in (idx + 1)..moves.len() {
+        if scores[next] > scores[best] {
+            best = next;
+        }
+    }
+    moves.swap(idx, best);
+    scores.swap(idx, best);
+    moves[idx]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+    use std::time::Instant;
+    use crate::board::Board;
+    use crate::piece::piecetype::PieceType;
+    use crate::search::{PvTable, Score, Search, SearchLimits, SearchResult, Searcher, DELTA_MARGIN, INFINITY, PIECE_VALUES};
+
+    fn search(fen: &str, depth: u32) -> SearchResult {
+        let board: Board = fen.parse().unwrap();
+        let limits = SearchLimits { depth: Some(depth), ..SearchLimits::default() };
+        Searcher::new().search(&board, &limits, &AtomicBool::new(false), |_| {})
+    }
+
+    #[test]
+    fn search_mate_in_one() {
+        let result = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+        assert_eq!(result.best_move.to_string(), "a1a8");
+        assert_eq!(result.score, Score::Mate(1));
+    }
+
+    #[test]
+    fn search_mate_in_two() {
+        // 1. Nf6+ gxf6 2. Bxf7#
+        let result = search("r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1", 4);
+        assert_eq!(result.score, Score::Mate(2));
+        assert_eq!(result.pv.len(), 3);
+    }
+
+    #[test]
+    fn search_getting_mated() {
+        // 1. Kg1 Rb1#
+        let result = search("6k1/8/8/8/8/1r6/r7/7K w - - 0 1", 3);
+        assert_eq!(result.score, Score::Mate(-1));
+        assert_eq!(result.best_move.to_string(), "h1g1");
+    }
+
+    #[test]
+    fn search_stalemate() {
+        let result = search("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3);
+        assert_eq!(result.score, Score::Centipawns(0));
+        assert!(result.best_move.is_null());
+    }
+
+    #[test]
+    fn search_insufficient_material_is_draw() {
+        // Kxf2 leaves a lone bishop, which is a draw however much the evaluation likes it
+        let result = search("4k3/8/8/8/8/8/5r2/4KB2 w - - 0 1", 3);
+        assert_eq!(result.best_move.to_string(), "e1f2");
+        assert_eq!(result.score, Score::Centipawns(0));
+    }
+
+    #[test]
+    fn search_wins_material() {
+        let result = search("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", 2);
+        assert_eq!(result.best_move.to_string(), "d1d5");
+        assert_eq!(result.depth, 2);
+    }
+
+    #[test]
+    fn search_quiescence_sees_recapture() {
+        // Without quiescence, depth 1 would grab the pawn on d5 and lose the queen to exd5
+        let result = search("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1", 1);
+        assert_ne!(result.best_move.to_string(), "d1d5");
+        assert!(matches!(result.score, Score::Centipawns(cp) if cp > 0));
+    }
+
+    #[test]
+    fn quiescence_keeps_promotions() {
+        // A queen down, but axb8=Q wins a queen and makes another
+        let board: Board = "1q2k3/P7/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+        let limits = SearchLimits::default();
+        let stop = AtomicBool::new(false);
+        let mut searcher = Searcher::new();
+        let mut search = Search {
+            board,
+            tt: &searcher.tt,
+            evaluator: &mut searcher.evaluator,
+            limits: &limits,
+            stop: &stop,
+            start: Instant::now(),
+            nodes: 0,
+            abortable: false,
+            aborted: false,
+            pv: PvTable::new(),
+            previous_pv: Vec::new(),
+        };
+
+        let alpha = search.evaluate() + PIECE_VALUES[PieceType::Q as usize] + DELTA_MARGIN + 1;
+        assert!(search.quiescence(0, alpha, INFINITY) > alpha);
+    }
+
+    #[test]
+    fn search_node_limit() {
+        let board: Board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        let limits = SearchLimits { nodes: Some(10_000), ..SearchLimits::default() };
+        let mut iterations = 0;
+        let result = Searcher::new().search(&board, &limits, &AtomicBool::new(false), |_| iterations += 1);
+
+        assert!(!result.best_move.is_null());
+        assert!(result.nodes < 20_000);
+        assert!(iterations >= 1);
+    }
+
+    #[test]
+    fn search_stopped() {
+        let board: Board = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse().unwrap();
+        let result = Searcher::new().search(&board, &SearchLimits::default(), &AtomicBool::new(true), |_| {});
+
+        // Depth 1 still completes so that there is always a move to play
+        assert_eq!(result.depth, 1);
+        assert!(!result.best_move.is_null());
+    }
+}
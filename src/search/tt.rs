@@ -0,0 +1,286 @@
+use std::mem;
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::board::moves::Move;
+use crate::search::MATE_BOUND;
+
+pub const DEFAULT_HASH_MIB: usize = 16;
+pub const MAX_HASH_MIB: usize = 65536;
+
+const BUCKET_SIZE: usize = 4;
+
+/// How a stored score relates to the true score of the position.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact = 1,
+    /// The search failed high: the true score is at least this.
+    Lower = 2,
+    /// The search failed low: the true score is at most this.
+    Upper = 3,
+}
+
+/// What the table knows about a position.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TtEntry {
+    pub best_move: Move,
+    pub depth: u32,
+    pub bound: Bound,
+    /// Mate scores are relative to the probing node, like every other search score.
+    pub score: i32,
+}
+
+/// One slot. The key is stored XORed with the data so that an entry torn by two threads
+/// writing at once fails verification instead of returning another position's data.
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Slots sharing a cache line; a position may be stored in any slot of its bucket.
+#[derive(Default)]
+#[repr(align(64))]
+struct Bucket {
+    slots: [Slot; BUCKET_SIZE],
+}
+
+// Data layout: move in bits 0-15, score in 16-31, depth in 32-39, bound in 40-41 (zero
+// for an empty slot) and the search age in 48-55.
+fn pack(mv: Move, score: i32, depth: u32, bound: Bound, age: u8) -> u64 {
+    u64::from(u16::from(mv))
+        | u64::from(score as i16 as u16) << 16
+        | u64::from(depth.min(u8::MAX as u32)) << 32
+        | (bound as u64) << 40
+        | u64::from(age) << 48
+}
+
+fn unpack_bound(data: u64) -> Option<Bound> {
+    match (data >> 40) & 0b11 {
+        1 => Some(Bound::Exact),
+        2 => Some(Bound::Lower),
+        3 => Some(Bound::Upper),
+        _ => None,
+    }
+}
+
+fn unpack_depth(data: u64) -> u32 {
+    ((data >> 32) & 0xff) as u32
+}
+
+fn unpack_age(data: u64) -> u8 {
+    (data >> 48) as u8
+}
+
+/// A shared, lock-free transposition table keyed on the board's Zobrist key.
+///
+/// Each position maps to a bucket of four slots. A store replaces the slot already holding
+/// the position if there is one, and otherwise the slot whose entry is least worth keeping:
+/// shallow entries and entries left over from earlier searches go first.
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    age: u8,
+}
+
+impl TranspositionTable {
+    /// A table using about `mib` mebibytes.
+    pub fn new(mib: usize) -> Self {
+        let mut tt = Self { buckets: Vec::new(), age: 0 };
+        tt.resize(mib);
+        tt
+    }
+
+    /// Reallocates the table with about `mib` mebibytes, dropping every entry.
+    pub fn resize(&mut self, mib: usize) {
+        let count = (mib.clamp(1, MAX_HASH_MIB) << 20) / mem::size_of::<Bucket>();
+        self.buckets = Vec::new();
+        self.buckets.resize_with(count, Bucket::default);
+        self.age = 0;
+    }
+
+    pub fn clear(&mut self) {
+        for slot in self.buckets.iter_mut().flat_map(|b| b.slots.iter_mut()) {
+            *slot = Slot::default();
+        }
+        self.age = 0;
+    }
+
+    /// Marks the start of a new search, so that entries from earlier searches are
+    /// replaced first.
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    /// Looks the position up. `ply` is the distance from the root, needed to turn stored
+    /// mate scores back into scores relative to the root.
+    pub fn probe(&self, key: u64, ply: usize) -> Option<TtEntry> {
+        self.bucket(key).slots.iter().find_map(|slot| {
+            let data = slot.data.load(Ordering::Relaxed);
+            if slot.key.load(Ordering::Relaxed) ^ data != key {
+                return None;
+            }
+            Some(TtEntry {
+                best_move: Move::try_from(data as u16).ok()?,
+                depth: unpack_depth(data),
+                bound: unpack_bound(data)?,
+                score: score_from_tt(i32::from((data >> 16) as u16 as i16), ply),
+            })
+        })
+    }
+
+    pub fn store(&self, key: u64, best_move: Move, depth: u32, bound: Bound, score: i32, ply: usize) {
+        let bucket = self.bucket(key);
+
+        let mut replace = &bucket.slots[0];
+        let mut replace_worth = i32::MAX;
+        let mut previous_move = Move::NULL;
+        for slot in bucket.slots.iter() {
+            let data = slot.data.load(Ordering::Relaxed);
+            if slot.key.load(Ordering::Relaxed) ^ data == key && unpack_bound(data).is_some() {
+                replace = slot;
+                previous_move = Move::try_from(data as u16).unwrap_or(Move::NULL);
+                break;
+            }
+
+            let worth = match unpack_bound(data) {
+                None => i32::MIN,
+                Some(_) => unpack_depth(data) as i32 - 8 * i32::from(self.age.wrapping_sub(unpack_age(data))),
+            };
+            if worth < replace_worth {
+                replace = slot;
+                replace_worth = worth;
+            }
+        }
+
+        // Keep the old move if this search of the position didn't find one
+        let best_move = if best_move.is_null() { previous_move } else { best_move };
+        let data = pack(best_move, score_to_tt(score, ply), depth, bound, self.age);
+        replace.key.store(key ^ data, Ordering::Relaxed);
+        replace.data.store(data, Ordering::Relaxed);
+    }
+
+    /// How full the table is, in permille, estimated from its first thousand slots as UCI's
+    /// `hashfull` expects.
+    pub fn hashfull(&self) -> usize {
+        let sample = self.buckets.iter().flat_map(|b| b.slots.iter()).take(1000);
+        let (mut total, mut used) = (0, 0);
+        for slot in sample {
+            let data = slot.data.load(Ordering::Relaxed);
+            total += 1;
+            if unpack_bound(data).is_some() && unpack_age(data) == self.age {
+                used += 1;
+            }
+        }
+        used * 1000 / total.max(1)
+    }
+
+    fn bucket(&self, key: u64) -> &Bucket {
+        // Multiply-shift maps the key onto any table size without a modulo
+        let idx = (u128::from(key) * self.buckets.len() as u128) >> 64;
+        &self.buckets[idx as usize]
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MIB)
+    }
+}
+
+/// Mate scores are stored relative to the stored position rather than to the root, since
+/// the same position can be reached at different plies.
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    }
+    else if score <= -MATE_BOUND {
+        score - ply as i32
+    }
+    else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    }
+    else if score <= -MATE_BOUND {
+        score + ply as i32
+    }
+    else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use crate::board::moves::{Move, MoveFlag};
+    use crate::board_representation::square::Square;
+    use crate::search::MATE;
+    use crate::search::tt::{Bound, TranspositionTable, TtEntry};
+
+    fn e2e4() -> Move {
+        Move::new(Square::try_from(('e', '2')).unwrap(), Square::try_from(('e', '4')).unwrap(), MoveFlag::DoublePush)
+    }
+
+    #[test]
+    fn tt_store_and_probe() {
+        let tt = TranspositionTable::new(1);
+        let key = 0x1234_5678_9abc_def0;
+        assert_eq!(tt.probe(key, 0), None);
+
+        tt.store(key, e2e4(), 7, Bound::Lower, -35, 3);
+        assert_eq!(tt.probe(key, 5), Some(TtEntry { best_move: e2e4(), depth: 7, bound: Bound::Lower, score: -35 }));
+        assert_eq!(tt.probe(key ^ 1, 0), None);
+
+        // A later search without a move keeps the old one
+        tt.store(key, Move::NULL, 9, Bound::Exact, 10, 0);
+        assert_eq!(tt.probe(key, 0).unwrap().best_move, e2e4());
+        assert_eq!(tt.probe(key, 0).unwrap().depth, 9);
+    }
+
+    #[test]
+    fn tt_mate_scores_are_ply_adjusted() {
+        let tt = TranspositionTable::new(1);
+        // Mate in 3 plies from a node at ply 4 is mate in 7 plies from the root
+        tt.store(42, Move::NULL, 5, Bound::Exact, MATE - 7, 4);
+        assert_eq!(tt.probe(42, 4).unwrap().score, MATE - 7);
+        // Reached again at ply 2, the same mate is 5 plies from the root
+        assert_eq!(tt.probe(42, 2).unwrap().score, MATE - 5);
+
+        tt.store(43, Move::NULL, 5, Bound::Exact, -MATE + 6, 6);
+        assert_eq!(tt.probe(43, 0).unwrap().score, -MATE);
+    }
+
+    #[test]
+    fn tt_replacement_prefers_old_and_shallow_entries() {
+        let mut tt = TranspositionTable::new(1);
+        let buckets = tt.buckets.len() as u128;
+        // Keys that all land in the first bucket
+        let keys: Vec<u64> = (1..=5_u128).map(|k| (k * (u128::from(u64::MAX) / buckets / 8)) as u64).collect();
+
+        for (depth, key) in keys.iter().take(4).enumerate() {
+            tt.store(*key, Move::NULL, depth as u32 + 10, Bound::Exact, 0, 0);
+        }
+        // The shallowest entry (depth 10) is the one replaced
+        tt.store(keys[4], Move::NULL, 1, Bound::Exact, 0, 0);
+        assert!(tt.probe(keys[0], 0).is_none());
+        assert!(tt.probe(keys[4], 0).is_some());
+
+        // After a few searches, stale entries are replaced before fresh ones, however deep
+        for _ in 0..4 {
+            tt.new_search();
+        }
+        tt.store(keys[0], Move::NULL, 1, Bound::Exact, 0, 0);
+        tt.store(keys[4], Move::NULL, 1, Bound::Exact, 0, 0);
+        assert!(tt.probe(keys[0], 0).is_some());
+        assert!(tt.probe(keys[1], 0).is_none());
+        assert!(tt.probe(keys[4], 0).is_some());
+
+        tt.clear();
+        assert!(keys.iter().all(|k| tt.probe(*k, 0).is_none()));
+        assert_eq!(tt.hashfull(), 0);
+    }
+}
@@ -0,0 +1,31 @@
+//! Fixtures shared by the unit tests.
+use std::convert::TryFrom;
+use crate::board::Board;
+use crate::board::moves::{Move, MoveList};
+use crate::board_representation::bitboard::BitBoard;
+use crate::board_representation::square::Square;
+
+/// The square with a name like `e4`.
+pub fn square(name: &str) -> Square {
+    let mut chars = name.chars();
+    Square::try_from((chars.next().unwrap(), chars.next().unwrap())).unwrap()
+}
+
+pub fn squares(names: &[&str]) -> BitBoard {
+    names.iter().fold(BitBoard::new(0), |bb, name| bb | BitBoard::from(square(name)))
+}
+
+/// The legal move written in long algebraic notation. Panics if there is none.
+pub fn find_move(board: &Board, name: &str) -> Move {
+    let mut moves = MoveList::new();
+    board.generate_legal(&mut moves);
+    *moves.iter().find(|m| m.to_string() == name).unwrap_or_else(|| panic!("illegal move: {}", name))
+}
+
+/// Plays moves written in long algebraic notation.
+pub fn play(board: &mut Board, moves: &[&str]) {
+    for name in moves.iter() {
+        let mv = find_move(board, name);
+        board.make_move(mv);
+    }
+}